@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use criterion::black_box;
 use criterion::criterion_group;
 use criterion::criterion_main;
+use criterion::BatchSize;
 use criterion::BenchmarkId;
 use criterion::Criterion;
 use sherwood_table::HashTable;
@@ -27,6 +28,21 @@ fn bench_insertion(c: &mut Criterion) {
       },
     );
 
+    group.bench_with_input(
+      BenchmarkId::new("sherwood_table_unique_unchecked", size),
+      &size,
+      |b, &s| {
+        b.iter(|| {
+          let mut table: HashTable<i32, i32> = HashTable::with_capacity(s);
+          for i_usize in 0..s {
+            let i = i_usize as i32;
+            table.insert_unique_unchecked(black_box(i), black_box(i * 2));
+          }
+          table
+        });
+      },
+    );
+
     group.bench_with_input(
       BenchmarkId::new("std_hashmap", size),
       &size,
@@ -78,6 +94,22 @@ fn bench_lookup(c: &mut Criterion) {
       },
     );
 
+    group.bench_with_input(
+      BenchmarkId::new("sherwood_table_hits_unchecked", size),
+      &size,
+      |b, &s| {
+        b.iter(|| {
+          let mut sum = 0;
+          for i_usize in 0..s {
+            let i = i_usize as i32;
+            // SAFETY: every key in 0..size was inserted above.
+            sum += unsafe { *sherwood_table.get_unchecked(&black_box(i)) };
+          }
+          sum
+        });
+      },
+    );
+
     group.bench_with_input(
       BenchmarkId::new("std_hashmap_hits", size),
       &size,
@@ -103,7 +135,7 @@ fn bench_lookup(c: &mut Criterion) {
           let mut count = 0;
           for i_usize in s..(s * 2) {
             let i = i_usize as i32;
-            if sherwood_table.get(&black_box(i)).is_none() {
+            if !sherwood_table.contains_key(&black_box(i)) {
               count += 1;
             }
           }
@@ -120,7 +152,7 @@ fn bench_lookup(c: &mut Criterion) {
           let mut count = 0;
           for i_usize in s..(s * 2) {
             let i = i_usize as i32;
-            if std_hashmap.get(&black_box(i)).is_none() {
+            if !std_hashmap.contains_key(&black_box(i)) {
               count += 1;
             }
           }
@@ -268,7 +300,7 @@ fn bench_iteration(c: &mut Criterion) {
       |b, &_s| {
         b.iter(|| {
           let mut sum = 0;
-          for (_, &val) in &sherwood_table {
+          for &val in sherwood_table.values() {
             sum += black_box(val);
           }
           sum
@@ -276,13 +308,28 @@ fn bench_iteration(c: &mut Criterion) {
       },
     );
 
+    let empty_table: HashTable<i32, i32> = HashTable::with_capacity(size);
+    group.bench_with_input(
+      BenchmarkId::new("sherwood_table_empty", size),
+      &size,
+      |b, &_s| {
+        b.iter(|| {
+          let mut sum = 0;
+          for (_, &val) in black_box(&empty_table) {
+            sum += val;
+          }
+          sum
+        });
+      },
+    );
+
     group.bench_with_input(
       BenchmarkId::new("std_hashmap", size),
       &size,
       |b, &_s| {
         b.iter(|| {
           let mut sum = 0;
-          for (_, &val) in &std_hashmap {
+          for &val in std_hashmap.values() {
             sum += black_box(val);
           }
           sum
@@ -399,6 +446,105 @@ fn bench_mixed_operations(c: &mut Criterion) {
   group.finish();
 }
 
+fn bench_large_key_resize(c: &mut Criterion) {
+  let mut group = c.benchmark_group("large_key_resize");
+
+  for size_usize in [100usize, 1_000, 10_000].iter() {
+    let size = *size_usize;
+    let mut table: HashTable<[u8; 1024], usize> = HashTable::new();
+    for i in 0..size {
+      let mut key = [0u8; 1024];
+      for (j, byte) in key.iter_mut().enumerate() {
+        *byte = ((i + j) % 256) as u8;
+      }
+      key[..8].copy_from_slice(&i.to_le_bytes());
+      table.insert(key, i);
+    }
+
+    group.bench_with_input(
+      BenchmarkId::new("sherwood_table", size),
+      &size,
+      |b, _| {
+        b.iter_batched(
+          || table.clone_layout(),
+          |mut table| {
+            let bucket_count = table.bucket_count();
+            table.resize(black_box(bucket_count * 2));
+            table
+          },
+          BatchSize::LargeInput,
+        );
+      },
+    );
+  }
+
+  group.finish();
+}
+
+// Shrinking a table that was filled and then mostly emptied, the usual
+// way to hand memory back after a burst.
+fn bench_compact(c: &mut Criterion) {
+  let mut group = c.benchmark_group("compact");
+
+  for size_usize in [1_000usize, 10_000, 100_000].iter() {
+    let size = *size_usize;
+    group.bench_with_input(
+      BenchmarkId::new("sherwood_table", size),
+      &size,
+      |b, &s| {
+        b.iter_batched(
+          || {
+            let mut table: HashTable<usize, usize> = HashTable::new();
+            for i in 0..s {
+              table.insert(i, i);
+            }
+            table.retain(|k, _| k % 10 == 0);
+            table
+          },
+          |mut table| {
+            table.shrink_to_fit();
+            black_box(table.memory_usage())
+          },
+          BatchSize::LargeInput,
+        );
+      },
+    );
+  }
+
+  group.finish();
+}
+
+// Run once with and once without `--features prefetch` to compare; the
+// benchmark id records which build produced the numbers.
+fn bench_large_value_lookup(c: &mut Criterion) {
+  let mut group = c.benchmark_group("large_value_lookup");
+  let name = if cfg!(feature = "prefetch") {
+    "sherwood_table_prefetch"
+  } else {
+    "sherwood_table"
+  };
+
+  let size = 10_000u64;
+  let mut table: HashTable<u64, [u64; 32]> = HashTable::new();
+  for i in 0..size {
+    table.insert(i, [i; 32]);
+  }
+
+  group.bench_with_input(BenchmarkId::new(name, size), &size, |b, &size| {
+    b.iter(|| {
+      let mut sum = 0u64;
+      for i in 0..size {
+        if let Some(value) = table.get(black_box(&i)) {
+          sum = sum.wrapping_add(value[0]);
+        }
+      }
+      sum
+    });
+  });
+
+  group.finish();
+}
+
 criterion_group!(
   benches,
   bench_insertion,
@@ -406,7 +552,9 @@ criterion_group!(
   bench_string_keys,
   bench_removal,
   bench_iteration,
-  bench_mixed_operations
+  bench_mixed_operations,
+  bench_large_key_resize,
+  bench_large_value_lookup,
+  bench_compact
 );
 criterion_main!(benches);
-
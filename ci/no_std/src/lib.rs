@@ -0,0 +1,49 @@
+//! Compile check that `sherwood_table` builds and is usable without `std`.
+
+#![no_std]
+
+use core::hash::BuildHasher;
+use core::hash::Hasher;
+
+use sherwood_table::HashTable;
+
+#[derive(Clone, Default)]
+pub struct FnvHasher(u64);
+
+impl Hasher for FnvHasher {
+  fn finish(&self) -> u64 {
+    self.0
+  }
+
+  fn write(&mut self, bytes: &[u8]) {
+    for &byte in bytes {
+      self.0 ^= byte as u64;
+      self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+  }
+}
+
+#[derive(Clone, Default)]
+pub struct FnvBuildHasher;
+
+impl BuildHasher for FnvBuildHasher {
+  type Hasher = FnvHasher;
+
+  fn build_hasher(&self) -> Self::Hasher {
+    FnvHasher(0xcbf2_9ce4_8422_2325)
+  }
+}
+
+pub fn sum_of_squares(n: u32) -> u64 {
+  let mut table: HashTable<u32, u64, FnvBuildHasher> = HashTable::new();
+  for i in 0..n {
+    table.insert(i, (i as u64) * (i as u64));
+  }
+  table.values().sum()
+}
+
+pub fn from_macro() -> usize {
+  let table: HashTable<u32, u32, FnvBuildHasher> =
+    sherwood_table::hashtable! { 1 => 10, 2 => 20 };
+  table.len()
+}
@@ -0,0 +1,113 @@
+use core::hash::BuildHasher;
+use core::hash::Hash;
+
+use crate::hash_table::HashPolicy;
+use crate::hash_table::HashTable;
+use crate::hash_table::Iter;
+use crate::hash_table::PowerOf2HashPolicy;
+
+/// A `HashTable` that recognises keys with a caller-supplied predicate
+/// instead of `K`'s `Eq`, e.g. to treat strings case-insensitively.
+///
+/// The hasher has to agree with the predicate: keys it considers equal
+/// must hash the same, or lookups will miss them.
+pub struct HashTableEq<
+  K,
+  V,
+  E,
+  #[cfg(feature = "std")] H = std::collections::hash_map::RandomState,
+  #[cfg(not(feature = "std"))] H,
+  P = PowerOf2HashPolicy,
+> where
+  K: Hash + Eq,
+  E: Fn(&K, &K) -> bool,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  table: HashTable<K, V, H, P>,
+  eq: E,
+}
+
+impl<K, V, E, H, P> HashTableEq<K, V, E, H, P>
+where
+  K: Hash + Eq,
+  E: Fn(&K, &K) -> bool,
+  H: BuildHasher + Default + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  pub fn new(eq: E) -> Self {
+    Self::with_hasher(eq, H::default())
+  }
+}
+
+impl<K, V, E, H, P> HashTableEq<K, V, E, H, P>
+where
+  K: Hash + Eq,
+  E: Fn(&K, &K) -> bool,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  pub fn with_hasher(eq: E, build_hasher: H) -> Self {
+    Self {
+      table: HashTable::with_hasher(build_hasher),
+      eq,
+    }
+  }
+
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.table.len()
+  }
+
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.table.is_empty()
+  }
+
+  /// Inserts `value` under `key`. If an equal key is already present its
+  /// value is replaced and returned, and the stored key is kept.
+  pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+    self.table.reserve(1);
+    let hash = self.table.hasher().hash_one(&key);
+    match self.table.index_by_hash(hash, |k| (self.eq)(k, &key)) {
+      Some(index) => {
+        Some(core::mem::replace(self.table.value_mut_at(index), value))
+      }
+      None => {
+        self.table.insert_absent(hash, key, value);
+        None
+      }
+    }
+  }
+
+  pub fn get(&self, key: &K) -> Option<&V> {
+    let hash = self.table.hasher().hash_one(key);
+    self.table.get_by_hash(hash, |k| (self.eq)(k, key))
+  }
+
+  pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+    let index = self.index_of(key)?;
+    Some(self.table.value_mut_at(index))
+  }
+
+  pub fn contains_key(&self, key: &K) -> bool {
+    self.index_of(key).is_some()
+  }
+
+  pub fn remove(&mut self, key: &K) -> Option<V> {
+    let index = self.index_of(key)?;
+    let (_, value) = self.table.remove_at(index);
+    self.table.shrink_if_sparse();
+    Some(value)
+  }
+
+  pub fn iter(&self) -> Iter<'_, K, V> {
+    self.table.iter()
+  }
+
+  #[inline]
+  fn index_of(&self, key: &K) -> Option<usize> {
+    let hash = self.table.hasher().hash_one(key);
+    self.table.index_by_hash(hash, |k| (self.eq)(k, key))
+  }
+}
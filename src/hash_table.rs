@@ -1,11 +1,37 @@
-use std::borrow::Borrow;
-use std::hash::BuildHasher;
-use std::hash::Hash;
-use std::hash::Hasher;
-use std::marker::PhantomData;
-use std::usize;
-
-pub(crate) const MIN_LOOKUPS: i8 = 64;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::BuildHasher;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+pub(crate) const MIN_LOOKUPS: i32 = 64;
+
+/// Upper bound on both the element count and the bucket count a table can
+/// be asked for. Anything above it is reported as a capacity overflow
+/// before any size arithmetic or policy rounding can wrap. In practice the
+/// bucket array's memory runs out well below this.
+pub const MAX_CAPACITY: usize = 1 << (usize::BITS - 2);
+const DEFAULT_MAX_LOAD_FACTOR: f32 = 0.5;
+
+// `desired_distance` of a slot whose entry was removed by `remove_fast`.
+// Lookups walk past it as if it were still occupied, so keys further along
+// stay reachable without shifting them back.
+pub(crate) const TOMBSTONE: i32 = i32::MIN;
+
+// `f64::ceil` lives in std; load factor maths only ever rounds non-negative
+// values, for which truncating and bumping is enough.
+#[inline]
+fn ceil_to_usize(value: f64) -> usize {
+  let truncated = value as usize;
+  if (truncated as f64) < value {
+    truncated.saturating_add(1)
+  } else {
+    truncated
+  }
+}
 
 pub(crate) trait Log2Ext {
   fn log2(self) -> i8;
@@ -18,7 +44,8 @@ impl Log2Ext for usize {
 
 #[derive(Debug)]
 pub(crate) struct HashEntry<T> {
-  pub(crate) desired_distance: i8,
+  pub(crate) desired_distance: i32,
+  pub(crate) hash: u64,
   pub(crate) value: Option<T>,
 }
 
@@ -26,6 +53,7 @@ impl<T> Default for HashEntry<T> {
   fn default() -> Self {
     Self {
       desired_distance: -1,
+      hash: 0,
       value: None,
     }
   }
@@ -35,22 +63,13 @@ impl<T: Clone> Clone for HashEntry<T> {
   fn clone(&self) -> Self {
     Self {
       desired_distance: self.desired_distance,
+      hash: self.hash,
       value: self.value.clone(),
     }
   }
 }
 
 impl<T> HashEntry<T> {
-  pub(crate) const END_VALUE: u8 = 0;
-
-  #[inline]
-  pub(crate) fn new(desired_distance: i8) -> Self {
-    Self {
-      desired_distance,
-      value: None,
-    }
-  }
-
   #[inline]
   pub(crate) fn empty() -> Self {
     Self::default()
@@ -63,19 +82,32 @@ impl<T> HashEntry<T> {
 
   #[inline]
   pub(crate) fn is_empty(&self) -> bool {
-    self.desired_distance < 0 && self.value.is_none()
+    self.desired_distance == -1 && self.value.is_none()
+  }
+
+  #[inline]
+  pub(crate) fn is_tombstone(&self) -> bool {
+    self.desired_distance == TOMBSTONE
   }
 
   #[inline]
   pub(crate) fn is_at_desired_position(&self) -> bool {
-    self.desired_distance <= 0
+    self.desired_distance == 0
   }
 }
 
 pub trait HashPolicy {
+  /// Picks the bucket count to use for at least `capacity` buckets.
   fn new_capacity(&self, capacity: usize) -> usize;
+  /// Maps `hash` to a bucket in `0..=num_slots`.
   fn hash_index(&self, hash: u64, num_slots: usize) -> usize;
+  /// Called by `HashTable::resize` once the new bucket count is known and
+  /// before any entry is rehashed, always right after `reset`. `smth` is the
+  /// new bucket count, i.e. `num_slots + 1` for every following
+  /// `hash_index` call.
   fn commit(&mut self, smth: u64);
+  /// Called by `HashTable::resize` right before `commit`, to drop any state
+  /// cached for the previous bucket count.
   fn reset(&mut self);
 }
 
@@ -100,6 +132,11 @@ impl HashPolicy for PowerOf2HashPolicy {
   }
   #[inline]
   fn hash_index(&self, hash: u64, num_slots: usize) -> usize {
+    debug_assert!(
+      (num_slots + 1).is_power_of_two(),
+      "PowerOf2HashPolicy needs a power-of-two bucket count, got {}",
+      num_slots + 1
+    );
     hash as usize & num_slots
   }
 
@@ -109,6 +146,228 @@ impl HashPolicy for PowerOf2HashPolicy {
   fn reset(&mut self) {}
 }
 
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FibonacciHashPolicy;
+
+impl FibonacciHashPolicy {
+  const GOLDEN_RATIO: u64 = 0x9E37_79B9_7F4A_7C15;
+}
+
+impl HashPolicy for FibonacciHashPolicy {
+  #[inline]
+  fn new_capacity(&self, capacity: usize) -> usize {
+    PowerOf2HashPolicy.new_capacity(capacity)
+  }
+
+  #[inline]
+  fn hash_index(&self, hash: u64, num_slots: usize) -> usize {
+    let shift = 64 - (num_slots + 1).log2() as u32;
+    hash
+      .wrapping_mul(Self::GOLDEN_RATIO)
+      .checked_shr(shift)
+      .unwrap_or(0) as usize
+  }
+
+  #[inline]
+  fn commit(&mut self, _smth: u64) {}
+  #[inline]
+  fn reset(&mut self) {}
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PrimeModulusHashPolicy {
+  modulus: u64,
+  reciprocal: u128,
+}
+
+impl PrimeModulusHashPolicy {
+  // Smallest prime >= 2^k for k in 2..63, capped by the largest prime that
+  // fits in `usize::MAX >> 1` on 64-bit targets.
+  const PRIMES: [u64; 62] = [
+    5,
+    11,
+    17,
+    37,
+    67,
+    131,
+    257,
+    521,
+    1_031,
+    2_053,
+    4_099,
+    8_209,
+    16_411,
+    32_771,
+    65_537,
+    131_101,
+    262_147,
+    524_309,
+    1_048_583,
+    2_097_169,
+    4_194_319,
+    8_388_617,
+    16_777_259,
+    33_554_467,
+    67_108_879,
+    134_217_757,
+    268_435_459,
+    536_870_923,
+    1_073_741_827,
+    2_147_483_659,
+    4_294_967_311,
+    8_589_934_609,
+    17_179_869_209,
+    34_359_738_421,
+    68_719_476_767,
+    137_438_953_481,
+    274_877_906_951,
+    549_755_813_911,
+    1_099_511_627_791,
+    2_199_023_255_579,
+    4_398_046_511_119,
+    8_796_093_022_237,
+    17_592_186_044_423,
+    35_184_372_088_891,
+    70_368_744_177_679,
+    140_737_488_355_333,
+    281_474_976_710_677,
+    562_949_953_421_381,
+    1_125_899_906_842_679,
+    2_251_799_813_685_269,
+    4_503_599_627_370_517,
+    9_007_199_254_740_997,
+    18_014_398_509_482_143,
+    36_028_797_018_963_971,
+    72_057_594_037_928_017,
+    144_115_188_075_855_881,
+    288_230_376_151_711_813,
+    576_460_752_303_423_619,
+    1_152_921_504_606_847_009,
+    2_305_843_009_213_693_967,
+    4_611_686_018_427_388_039,
+    9_223_372_036_854_775_783,
+  ];
+
+  #[inline]
+  fn next_prime(capacity: usize) -> Option<usize> {
+    let capacity = capacity as u64;
+    let rung = Self::PRIMES.partition_point(|&prime| prime < capacity);
+    Self::PRIMES
+      .get(rung)
+      .and_then(|&prime| usize::try_from(prime).ok())
+  }
+
+  // Lemire's fastmod: `hash % modulus` via a precomputed 128-bit reciprocal.
+  #[inline]
+  fn fast_mod(&self, hash: u64) -> u64 {
+    let low_bits = self.reciprocal.wrapping_mul(hash as u128);
+    let bottom_half = ((low_bits as u64) as u128 * self.modulus as u128) >> 64;
+    let top_half = (low_bits >> 64) * self.modulus as u128;
+    ((bottom_half + top_half) >> 64) as u64
+  }
+}
+
+impl HashPolicy for PrimeModulusHashPolicy {
+  #[inline]
+  fn new_capacity(&self, capacity: usize) -> usize {
+    Self::next_prime(capacity).unwrap_or(capacity)
+  }
+
+  #[inline]
+  fn hash_index(&self, hash: u64, num_slots: usize) -> usize {
+    let modulus = num_slots as u64 + 1;
+    if modulus == self.modulus {
+      self.fast_mod(hash) as usize
+    } else {
+      (hash % modulus) as usize
+    }
+  }
+
+  #[inline]
+  fn commit(&mut self, smth: u64) {
+    if smth == 0 {
+      self.reset();
+      return;
+    }
+    self.modulus = smth;
+    self.reciprocal = (u128::MAX / smth as u128).wrapping_add(1);
+  }
+
+  #[inline]
+  fn reset(&mut self) {
+    self.modulus = 0;
+    self.reciprocal = 0;
+  }
+}
+
+/// Grows the table by `factor` per resize instead of doubling, trading more
+/// frequent rehashes for less slack in large tables. Bucket counts are not
+/// rounded to any particular shape, so indexing is a plain modulo.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GeometricHashPolicy {
+  factor: f32,
+  bucket_count: usize,
+}
+
+impl GeometricHashPolicy {
+  pub fn new(factor: f32) -> Self {
+    assert!(
+      factor > 1.0,
+      "growth factor must be greater than 1, got {}",
+      factor
+    );
+    Self {
+      factor,
+      bucket_count: 0,
+    }
+  }
+
+  #[inline]
+  pub fn factor(&self) -> f32 {
+    self.factor
+  }
+}
+
+impl Default for GeometricHashPolicy {
+  fn default() -> Self {
+    Self::new(1.5)
+  }
+}
+
+impl HashPolicy for GeometricHashPolicy {
+  // Requests at or below the committed bucket count are taken as they are,
+  // so shrinking and no-op resizes stay exact. Growth steps up from the
+  // committed count by `factor` until the request fits.
+  #[inline]
+  fn new_capacity(&self, capacity: usize) -> usize {
+    let capacity = capacity.max(crate::MIN_LOOKUPS as usize);
+    if self.bucket_count == 0 || capacity <= self.bucket_count {
+      return capacity;
+    }
+
+    let mut next = self.bucket_count;
+    while next < capacity {
+      next = ceil_to_usize(next as f64 * self.factor as f64).max(next + 1);
+    }
+    next
+  }
+
+  #[inline]
+  fn hash_index(&self, hash: u64, num_slots: usize) -> usize {
+    (hash % (num_slots as u64 + 1)) as usize
+  }
+
+  #[inline]
+  fn commit(&mut self, smth: u64) {
+    self.bucket_count = smth as usize;
+  }
+
+  #[inline]
+  fn reset(&mut self) {
+    self.bucket_count = 0;
+  }
+}
+
 pub trait SelectHashPolicy {
   type Policy: HashPolicy + Default + Clone;
 }
@@ -155,11 +414,110 @@ where
   }
 }
 
-#[derive(Debug)]
+/// A small multiply-rotate hasher in the style of rustc's FxHash, with a
+/// finalizer that mixes the high bits back down so `PowerOf2HashPolicy`'s
+/// low-bit mask still sees a good spread.
+///
+/// This is **not** HashDoS-resistant: the seed only perturbs the output,
+/// and anyone who can pick keys can still force collisions. Use it for
+/// trusted keys only and keep `RandomState` for anything user-controlled.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FxLikeBuildHasher {
+  seed: u64,
+}
+
+impl FxLikeBuildHasher {
+  pub fn with_seed(seed: u64) -> Self {
+    Self { seed }
+  }
+}
+
+impl BuildHasher for FxLikeBuildHasher {
+  type Hasher = FxLikeHasher;
+
+  #[inline]
+  fn build_hasher(&self) -> Self::Hasher {
+    FxLikeHasher { state: self.seed }
+  }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FxLikeHasher {
+  state: u64,
+}
+
+impl FxLikeHasher {
+  const MULTIPLIER: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+  #[inline]
+  fn add_to_hash(&mut self, word: u64) {
+    self.state =
+      (self.state.rotate_left(5) ^ word).wrapping_mul(Self::MULTIPLIER);
+  }
+}
+
+impl Hasher for FxLikeHasher {
+  #[inline]
+  fn write(&mut self, bytes: &[u8]) {
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in &mut chunks {
+      let mut word = [0; 8];
+      word.copy_from_slice(chunk);
+      self.add_to_hash(u64::from_le_bytes(word));
+    }
+    let rest = chunks.remainder();
+    if !rest.is_empty() {
+      let mut word = [0; 8];
+      word[..rest.len()].copy_from_slice(rest);
+      self.add_to_hash(u64::from_le_bytes(word));
+    }
+  }
+
+  #[inline]
+  fn write_u8(&mut self, i: u8) {
+    self.add_to_hash(i as u64);
+  }
+
+  #[inline]
+  fn write_u16(&mut self, i: u16) {
+    self.add_to_hash(i as u64);
+  }
+
+  #[inline]
+  fn write_u32(&mut self, i: u32) {
+    self.add_to_hash(i as u64);
+  }
+
+  #[inline]
+  fn write_u64(&mut self, i: u64) {
+    self.add_to_hash(i);
+  }
+
+  #[inline]
+  fn write_usize(&mut self, i: usize) {
+    self.add_to_hash(i as u64);
+  }
+
+  // The multiply only carries entropy upwards, so keys that differ in their
+  // high bits alone would all land in one bucket under a low-bit mask.
+  // MurmurHash3's fmix64 spreads every input bit over the whole output.
+  #[inline]
+  fn finish(&self) -> u64 {
+    let mut h = self.state;
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^= h >> 33;
+    h
+  }
+}
+
 pub struct HashTable<
   K,
   V,
-  H = std::collections::hash_map::RandomState,
+  #[cfg(feature = "std")] H = std::collections::hash_map::RandomState,
+  #[cfg(not(feature = "std"))] H,
   P = PowerOf2HashPolicy,
 > where
   K: Hash + Eq,
@@ -170,12 +528,22 @@ pub struct HashTable<
 
   buckets: Vec<HashEntry<(K, V)>>,
   num_slots: usize,
+  current_bucket_count: usize,
   num_elements: usize,
-  max_lookups: i8,
+  num_tombstones: usize,
+  max_lookups: i32,
   max_load_factor: f32,
+  min_load_factor: f32,
+  shrink_ratio: f32,
+  removals_since_resize: usize,
+  resize_hook: Option<ResizeHook>,
   _marker: PhantomData<(K, V)>,
 }
 
+// Called with `(old_bucket_count, new_bucket_count)` after a rehash that
+// changed the bucket count.
+type ResizeHook = Box<dyn FnMut(usize, usize) + Send + Sync>;
+
 impl<K, V, H, P> Default for HashTable<K, V, H, P>
 where
   K: Hash + Eq,
@@ -187,6 +555,18 @@ where
   }
 }
 
+impl<K, V, H, P> core::fmt::Debug for HashTable<K, V, H, P>
+where
+  K: Hash + Eq + core::fmt::Debug,
+  V: core::fmt::Debug,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_map().entries(self.iter()).finish()
+  }
+}
+
 impl<K, V, H, P> Clone for HashTable<K, V, H, P>
 where
   K: Hash + Eq + Clone,
@@ -195,18 +575,228 @@ where
   P: HashPolicy + Default + Clone,
 {
   fn clone(&self) -> Self {
-    let mut new_table = Self::with_capacity_and_hasher_and_policy(
-      self.capacity(),
+    let mut new_table = Self::with_hasher_and_policy(
       self.build_hasher.build_hasher.clone(),
       self.build_hasher.policy.clone(),
     );
 
+    new_table.set_max_load_factor(self.max_load_factor);
+    new_table.min_load_factor = self.min_load_factor;
+    new_table.shrink_ratio = self.shrink_ratio;
+    new_table.reserve(self.len());
+
     for (k, v) in self.iter() {
       new_table.insert(k.clone(), v.clone());
     }
 
     new_table
   }
+
+  // Reuses our bucket array when it is big enough for `source` at its load
+  // factor. Source keys are already unique and their hashes are cached, so
+  // they go straight into place without lookups or rehashing.
+  fn clone_from(&mut self, source: &Self) {
+    let needed =
+      Self::min_buckets_for(source.num_elements, source.max_load_factor);
+    if self.buckets.is_empty() || self.current_bucket_count < needed {
+      *self = source.clone();
+      return;
+    }
+
+    self.clear();
+    self
+      .build_hasher
+      .build_hasher
+      .clone_from(&source.build_hasher.build_hasher);
+    self
+      .build_hasher
+      .policy
+      .clone_from(&source.build_hasher.policy);
+    self.build_hasher.policy.reset();
+    self
+      .build_hasher
+      .policy
+      .commit(self.current_bucket_count as u64);
+    self.max_load_factor = source.max_load_factor;
+    self.min_load_factor = source.min_load_factor;
+    self.shrink_ratio = source.shrink_ratio;
+    self.max_lookups = Self::compute_max_lookups(
+      self.current_bucket_count,
+      self.max_load_factor,
+    );
+
+    for entry in &source.buckets {
+      if let Some((k, v)) = entry.value.as_ref() {
+        self.insert_during_resize(entry.hash, k.clone(), v.clone());
+      }
+    }
+  }
+}
+
+impl<K, V, H, P> PartialEq for HashTable<K, V, H, P>
+where
+  K: Hash + Eq,
+  V: PartialEq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn eq(&self, other: &Self) -> bool {
+    if self.len() != other.len() {
+      return false;
+    }
+
+    self
+      .iter()
+      .all(|(key, value)| other.get(key).is_some_and(|v| *value == *v))
+  }
+}
+
+impl<K, V, H, P> Eq for HashTable<K, V, H, P>
+where
+  K: Hash + Eq,
+  V: Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+}
+
+// Entries are hashed one by one with a fixed-key hasher rather than the
+// table's own, so equal tables hash equally even when their `H` instances
+// are seeded differently, and XOR-ed so bucket order doesn't matter.
+#[cfg(feature = "std")]
+impl<K, V, H, P> Hash for HashTable<K, V, H, P>
+where
+  K: Hash + Eq,
+  V: Hash,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn hash<S: Hasher>(&self, state: &mut S) {
+    let build_hasher =
+      core::hash::BuildHasherDefault::<std::hash::DefaultHasher>::default();
+    let combined = self
+      .iter()
+      .fold(0u64, |acc, entry| acc ^ build_hasher.hash_one(entry));
+
+    state.write_usize(self.len());
+    state.write_u64(combined);
+  }
+}
+
+impl<K, V, H, P, Q> core::ops::Index<&Q> for HashTable<K, V, H, P>
+where
+  K: Hash + Eq + Borrow<Q>,
+  Q: Hash + Eq + ?Sized,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  type Output = V;
+
+  #[inline]
+  fn index(&self, key: &Q) -> &V {
+    self.get(key).expect("no entry found for key")
+  }
+}
+
+impl<K, V, H, P> FromIterator<(K, V)> for HashTable<K, V, H, P>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Default + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+    let mut table = Self::new();
+    table.extend(iter);
+    table
+  }
+}
+
+impl<K, V, H, P, const N: usize> From<[(K, V); N]> for HashTable<K, V, H, P>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Default + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn from(pairs: [(K, V); N]) -> Self {
+    let mut table = Self::new();
+    table.reserve(N);
+    for (k, v) in pairs {
+      table.insert(k, v);
+    }
+    table
+  }
+}
+
+#[cfg(feature = "std")]
+impl<K, V, H, P> From<std::collections::HashMap<K, V, H>>
+  for HashTable<K, V, H, P>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn from(map: std::collections::HashMap<K, V, H>) -> Self {
+    let mut table = Self::with_hasher(map.hasher().clone());
+    table.reserve(map.len());
+    table.extend(map);
+    table
+  }
+}
+
+#[cfg(feature = "std")]
+impl<K, V, H, P> From<HashTable<K, V, H, P>>
+  for std::collections::HashMap<K, V, H>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn from(table: HashTable<K, V, H, P>) -> Self {
+    let mut map = std::collections::HashMap::with_capacity_and_hasher(
+      table.len(),
+      table.hasher().clone(),
+    );
+    map.extend(table);
+    map
+  }
+}
+
+impl<K, V, H, P> Extend<(K, V)> for HashTable<K, V, H, P>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+    let iter = iter.into_iter();
+    self.reserve(iter.size_hint().0);
+    for (k, v) in iter {
+      self.insert(k, v);
+    }
+  }
+}
+
+impl<K, V> HashTable<K, V, FxLikeBuildHasher, PowerOf2HashPolicy>
+where
+  K: Hash + Eq,
+{
+  /// An empty table using `FxLikeBuildHasher`. Faster than the default for
+  /// small keys, but see the hasher's docs before using it on untrusted
+  /// input.
+  pub fn fast() -> Self {
+    Self::with_hasher(FxLikeBuildHasher::default())
+  }
+}
+
+impl<K, V, H> HashTable<K, V, H, <H as SelectHashPolicy>::Policy>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+{
+  /// An empty table using the policy `SelectHashPolicy` picks for `H`.
+  pub fn with_selected_policy(build_hasher: H) -> Self {
+    Self::with_hasher_and_policy(build_hasher, Default::default())
+  }
 }
 
 impl<K, V, H, P> HashTable<K, V, H, P>
@@ -222,6 +812,10 @@ where
   pub fn with_capacity(capacity: usize) -> Self {
     Self::with_capacity_and_hasher(capacity, H::default())
   }
+
+  pub fn with_max_load_factor(capacity: usize, factor: f32) -> Self {
+    Self::with_max_load_factor_and_hasher(capacity, factor, H::default())
+  }
 }
 
 impl<K, V, H, P> HashTable<K, V, H, P>
@@ -249,21 +843,54 @@ where
     )
   }
 
+  #[inline]
+  pub fn with_max_load_factor_and_hasher(
+    capacity: usize,
+    factor: f32,
+    build_hasher: H,
+  ) -> Self {
+    let mut table = Self::with_hasher(build_hasher);
+    table.set_max_load_factor(factor);
+    table.reserve(capacity);
+    table
+  }
+
+  /// Sizes the table up front so that `capacity` elements fit at the
+  /// default load factor without a resize.
   #[inline]
   pub fn with_capacity_and_hasher_and_policy(
     capacity: usize,
     build_hasher: H,
     policy: P,
   ) -> Self {
-    Self {
+    let mut table = Self {
       build_hasher: BuildHasherWrapper::new(build_hasher, policy.clone()),
-      buckets: Vec::with_capacity(capacity),
+      buckets: Vec::new(),
       num_slots: 0,
+      current_bucket_count: 0,
       num_elements: 0,
+      num_tombstones: 0,
       max_lookups: MIN_LOOKUPS - 1,
-      max_load_factor: 0.5f32,
+      max_load_factor: DEFAULT_MAX_LOAD_FACTOR,
+      min_load_factor: 0.0f32,
+      shrink_ratio: 0.0f32,
+      removals_since_resize: 0,
+      resize_hook: None,
       _marker: PhantomData,
-    }
+    };
+    table.reserve(capacity);
+    table
+  }
+
+  /// Like `collect`, for hashers and policies that aren't `Default`. The
+  /// table is sized up front from the iterator's lower size bound.
+  pub fn from_iter_with_hasher<I>(iter: I, build_hasher: H, policy: P) -> Self
+  where
+    I: IntoIterator<Item = (K, V)>,
+  {
+    let mut table = Self::with_hasher_and_policy(build_hasher, policy);
+    table.extend(iter);
+    table
   }
 
   #[inline]
@@ -276,104 +903,489 @@ where
     self.num_elements == 0
   }
 
+  /// Number of elements the table can hold before the next insertion
+  /// triggers a resize, i.e. `bucket_count() * max_load_factor()` rounded
+  /// down, with the factor capped at 0.99 as growth does. Zero for a table
+  /// that has not allocated yet.
   #[inline]
   pub fn capacity(&self) -> usize {
-    if self.num_slots == 0 {
-      0
-    } else {
-      self.num_slots + 1
+    (self.bucket_count() as f64
+      * Self::growth_load_factor(self.max_load_factor)) as usize
+  }
+
+  /// The bucket count a fresh table with the default policy picks to hold
+  /// `len` elements at the default `max_load_factor` of 0.5, so roughly
+  /// `len / 0.5` rounded up to what the policy allows. `with_capacity(len)`
+  /// already sizes for this, so passing either to it never resizes while
+  /// inserting `len` elements; this is for reasoning about memory up front.
+  pub fn capacity_for(len: usize) -> usize {
+    if len == 0 {
+      return 0;
+    }
+    let min_buckets = Self::min_buckets_for(len, DEFAULT_MAX_LOAD_FACTOR);
+    P::default().new_capacity(min_buckets.max(MIN_LOOKUPS as usize))
+  }
+
+  /// Number of home buckets the hash policy indexes into. The backing
+  /// storage is `MIN_LOOKUPS` slots longer than this so probes can run past
+  /// the last bucket. Zero for a table that has not allocated yet.
+  #[inline]
+  pub fn bucket_count(&self) -> usize {
+    self.current_bucket_count
+  }
+
+  /// Total bytes used by the table, not just its heap: the inline size,
+  /// `size_of::<HashTable<..>>()`, plus the heap held by the bucket array,
+  /// so never less than the inline size. Subtract that for the heap bytes
+  /// alone. Each bucket stores its distance, cached hash and an
+  /// `Option<(K, V)>` including any padding and discriminant, so the heap
+  /// part is `size_of::<(K, V)>()` plus some per-slot overhead, times
+  /// `bucket_count() + 64`, and zero until the first allocation.
+  pub fn memory_usage(&self) -> usize {
+    core::mem::size_of::<Self>()
+      + self.buckets.capacity() * core::mem::size_of::<HashEntry<(K, V)>>()
+  }
+
+  #[inline]
+  pub fn max_load_factor(&self) -> f32 {
+    self.max_load_factor
+  }
+
+  /// Sets the load past which the table grows. `factor` must be in
+  /// `(0, 1)` and more than twice `min_load_factor()`; see
+  /// `set_min_load_factor`.
+  #[inline]
+  pub fn set_max_load_factor(&mut self, factor: f32) {
+    assert!(
+      factor > 0.0 && factor < 1.0,
+      "max load factor must be in (0, 1), got {}",
+      factor
+    );
+    assert!(
+      factor > self.min_load_factor * 2.0,
+      "max load factor must be more than twice the min load factor {}, got {}",
+      self.min_load_factor,
+      factor
+    );
+    self.max_load_factor = factor;
+    self.reserve(0);
+    if !self.buckets.is_empty() {
+      self.max_lookups =
+        Self::compute_max_lookups(self.bucket_count(), self.max_load_factor);
+    }
+  }
+
+  #[inline]
+  pub fn min_load_factor(&self) -> f32 {
+    self.min_load_factor
+  }
+
+  /// Enables shrinking on `remove`: once fewer than half of
+  /// `bucket_count() * factor` buckets are occupied, the table shrinks.
+  /// Waiting for half the floor keeps a table hovering around it from
+  /// resizing back and forth. `factor` must be in
+  /// `[0, max_load_factor() / 2)`, so that point stays below the quarter of
+  /// `max_load_factor` a shrink leaves room for; see `shrink_if_sparse`.
+  /// `0.0`, the default, disables this.
+  #[inline]
+  pub fn set_min_load_factor(&mut self, factor: f32) {
+    let limit = self.max_load_factor / 2.0;
+    assert!(
+      (0.0..limit).contains(&factor),
+      "min load factor must be in [0, {}), got {}",
+      limit,
+      factor
+    );
+    self.min_load_factor = factor;
+  }
+
+  #[inline]
+  pub fn shrink_ratio(&self) -> f32 {
+    self.shrink_ratio
+  }
+
+  /// Enables shrinking on `remove` by removal count rather than load: once
+  /// more than `bucket_count() * ratio` entries have been removed since the
+  /// last resize, the table shrinks, once, provided it is below a quarter
+  /// of `max_load_factor` at that point. Either way the count starts over.
+  /// `0.0`, the default, disables this; `0.5` is a reasonable setting.
+  #[inline]
+  pub fn set_shrink_ratio(&mut self, ratio: f32) {
+    assert!(
+      ratio >= 0.0 && ratio.is_finite(),
+      "shrink ratio must be finite and non-negative, got {}",
+      ratio
+    );
+    self.shrink_ratio = ratio;
+  }
+
+  /// Registers `hook` to be called with `(old_bucket_count,
+  /// new_bucket_count)` whenever a resize changes the bucket count,
+  /// replacing any previous hook. Clones of the table do not inherit it.
+  pub fn set_resize_hook<F>(&mut self, hook: F)
+  where
+    F: FnMut(usize, usize) + Send + Sync + 'static,
+  {
+    self.resize_hook = Some(Box::new(hook));
+  }
+
+  #[inline]
+  pub fn clear(&mut self) {
+    if self.num_elements == 0 && self.num_tombstones == 0 {
+      return;
+    }
+
+    for entry in self.buckets.iter_mut() {
+      if !entry.is_empty() {
+        *entry = HashEntry::empty();
+      }
     }
+    self.num_elements = 0;
+    self.num_tombstones = 0;
   }
 
   #[inline]
-  fn calculate_required_vec_len(capacity: usize, max_lookups: i8) -> usize {
+  fn calculate_required_vec_len(capacity: usize) -> usize {
     if capacity == 0 {
       return 0;
     }
-    capacity + (max_lookups as usize)
+    capacity + (MIN_LOOKUPS as usize)
   }
 
+  // A probe sequence can never be longer than the number of elements in the
+  // table, and the load factor caps that at `num_buckets * max_load_factor`.
+  // Bounding probes by that count means a heavily colliding key set just
+  // makes lookups slower instead of forcing resizes that can't separate
+  // the keys anyway.
   #[inline]
-  fn compute_max_lookups(num_buckets: usize) -> i8 {
+  fn compute_max_lookups(num_buckets: usize, max_load_factor: f32) -> i32 {
     if num_buckets == 0 {
-      MIN_LOOKUPS - 1
-    } else {
-      let log2_val = usize::BITS - num_buckets.leading_zeros() - 1;
-      (log2_val as i8).max(MIN_LOOKUPS)
+      return MIN_LOOKUPS - 1;
     }
+
+    let max_elements =
+      num_buckets as f64 * Self::growth_load_factor(max_load_factor);
+    ceil_to_usize(max_elements).clamp(MIN_LOOKUPS as usize, i32::MAX as usize)
+      as i32
   }
 
   #[inline]
-  fn hash_key<Q: ?Sized>(&self, key: &Q) -> u64
+  fn hash_key<Q>(&self, key: &Q) -> u64
   where
     K: Borrow<Q>,
-    Q: Hash,
+    Q: Hash + ?Sized,
   {
-    let mut hasher = self.build_hasher.build_hasher();
-    key.hash(&mut hasher);
-    hasher.finish()
+    self.build_hasher.hash_one(key)
   }
 
   #[inline]
-  fn desired_index<Q: ?Sized>(&self, key: &Q) -> usize
+  fn desired_index<Q>(&self, key: &Q) -> usize
   where
     K: Borrow<Q>,
-    Q: Hash,
+    Q: Hash + ?Sized,
   {
     let hash = self.hash_key(key);
-    if self.capacity() == 0 {
+    if self.bucket_count() == 0 {
       return 0;
     }
     self.build_hasher.policy.hash_index(hash, self.num_slots)
   }
 
   #[inline]
-  fn keys_equal<Q: ?Sized>(&self, query_key: &Q, entry_key: &K) -> bool
+  fn keys_equal<Q>(&self, query_key: &Q, entry_key: &K) -> bool
   where
     K: Borrow<Q>,
-    Q: Hash + Eq,
+    Q: Hash + Eq + ?Sized,
   {
     entry_key.borrow() == query_key
   }
 
+  // The load factor growth actually works to. Capped below 1 so a full
+  // table always keeps an empty slot for probes to stop at.
+  #[inline]
+  fn growth_load_factor(max_load_factor: f32) -> f64 {
+    (max_load_factor as f64).min(0.99)
+  }
+
+  #[inline]
+  fn min_buckets_for(num_elements: usize, max_load_factor: f32) -> usize {
+    ceil_to_usize(
+      num_elements as f64 / Self::growth_load_factor(max_load_factor),
+    )
+  }
+
+  #[inline]
+  pub fn reserve(&mut self, additional: usize) {
+    self.reserve_exact(additional);
+  }
+
+  /// Grows the table to the smallest bucket count the policy allows that
+  /// holds `len() + additional` elements at the current load factor, with
+  /// no extra headroom for later insertions. `reserve` currently sizes the
+  /// same way, but unlike it this is guaranteed never to over-allocate.
+  ///
+  /// Tombstones left by `remove_fast` count towards the load factor too.
+  /// When they are what pushes the table over it, the table is rebuilt at
+  /// its current size if that leaves it at most half full, and moves up
+  /// one size otherwise, so a table churning near its limit doesn't rehash
+  /// on every insertion.
   #[inline]
-  fn reserve(&mut self, additional: usize) {
-    let new_num_elements = self.num_elements.checked_add(additional).unwrap();
-    let new_num_buckets = (new_num_elements as f64
-      / (self.max_load_factor as f64).min(0.99))
-    .ceil() as usize;
+  pub fn reserve_exact(&mut self, additional: usize) {
+    if let Err(err) = self.try_reserve(additional) {
+      panic!("{}", err);
+    }
+  }
 
-    if new_num_buckets > self.capacity() {
+  /// Like `reserve_exact`, but reports capacity overflow and allocation
+  /// failure instead of panicking. The table is unchanged on error.
+  #[inline]
+  pub fn try_reserve(
+    &mut self,
+    additional: usize,
+  ) -> Result<(), TryReserveError> {
+    let new_num_elements = self
+      .num_elements
+      .checked_add(additional)
+      .filter(|&num_elements| num_elements <= MAX_CAPACITY)
+      .ok_or(TryReserveError::Overflow)?;
+    let new_num_buckets =
+      Self::min_buckets_for(new_num_elements, self.max_load_factor);
+
+    if new_num_buckets > self.bucket_count() {
       let new_capacity_hint = new_num_buckets.max(MIN_LOOKUPS as usize);
-      self.resize(new_capacity_hint);
+      self.try_resize(new_capacity_hint)?;
+    } else if self.num_tombstones > 0
+      && Self::min_buckets_for(
+        new_num_elements.saturating_add(self.num_tombstones),
+        self.max_load_factor,
+      ) > self.bucket_count()
+    {
+      if new_num_elements <= self.capacity() / 2 {
+        self.try_rehash(self.current_bucket_count)?;
+      } else {
+        self.try_resize(self.current_bucket_count + 1)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Re-places every entry into a bucket array of the same size, clearing
+  /// the tombstones left by `remove_fast` and bringing every key back as
+  /// close to its ideal bucket as Robin Hood placement allows. Unlike
+  /// `resize`, this always does the work, even when the bucket count
+  /// wouldn't change.
+  pub fn rebuild(&mut self) {
+    if self.buckets.is_empty() {
+      return;
+    }
+    if let Err(err) = self.try_rehash(self.current_bucket_count) {
+      panic!("{}", err);
+    }
+  }
+
+  /// Consumes the table and builds one mapping each value back to its key,
+  /// with a clone of the hasher and a fresh policy. If several keys share
+  /// a value, the one seen last in bucket order wins.
+  pub fn invert(self) -> HashTable<V, K, H, P>
+  where
+    V: Hash + Eq,
+  {
+    let mut inverted = HashTable::with_hasher_and_policy(
+      self.build_hasher.build_hasher.clone(),
+      P::default(),
+    );
+    inverted.set_max_load_factor(self.max_load_factor);
+    inverted.min_load_factor = self.min_load_factor;
+    inverted.shrink_ratio = self.shrink_ratio;
+    inverted.reserve(self.len());
+
+    for (key, value) in self {
+      inverted.insert(value, key);
+    }
+    inverted
+  }
+
+  /// Moves the contents out into a new table with the same hasher, policy
+  /// and load factors, leaving `self` empty with a fresh bucket array of
+  /// the same size, so refilling it does not start from scratch.
+  pub fn take(&mut self) -> Self {
+    let mut fresh_buckets = Vec::new();
+    fresh_buckets.resize_with(self.buckets.len(), HashEntry::empty);
+
+    Self {
+      build_hasher: self.build_hasher.clone(),
+      buckets: core::mem::replace(&mut self.buckets, fresh_buckets),
+      num_slots: self.num_slots,
+      current_bucket_count: self.current_bucket_count,
+      num_elements: core::mem::take(&mut self.num_elements),
+      num_tombstones: core::mem::take(&mut self.num_tombstones),
+      max_lookups: self.max_lookups,
+      max_load_factor: self.max_load_factor,
+      min_load_factor: self.min_load_factor,
+      shrink_ratio: self.shrink_ratio,
+      removals_since_resize: core::mem::take(&mut self.removals_since_resize),
+      resize_hook: None,
+      _marker: PhantomData,
+    }
+  }
+
+  /// A copy with exactly the same bucket layout: every entry in the same
+  /// slot at the same distance, tombstones included. `clone` re-inserts
+  /// and may place colliding keys differently, which matters when
+  /// comparing probe statistics or benchmarking. The resize hook is not
+  /// copied.
+  pub fn clone_layout(&self) -> Self
+  where
+    K: Clone,
+    V: Clone,
+  {
+    Self {
+      build_hasher: self.build_hasher.clone(),
+      buckets: self.buckets.clone(),
+      num_slots: self.num_slots,
+      current_bucket_count: self.current_bucket_count,
+      num_elements: self.num_elements,
+      num_tombstones: self.num_tombstones,
+      max_lookups: self.max_lookups,
+      max_load_factor: self.max_load_factor,
+      min_load_factor: self.min_load_factor,
+      shrink_ratio: self.shrink_ratio,
+      removals_since_resize: self.removals_since_resize,
+      resize_hook: None,
+      _marker: PhantomData,
     }
   }
 
   #[inline]
-  pub fn resize(&mut self, capacity_hint: usize) {
+  pub fn clear_and_shrink(&mut self) {
+    self.clear();
+    self.shrink_to_fit();
+  }
+
+  #[inline]
+  pub fn shrink_to_fit(&mut self) {
+    self.shrink_to(0);
+  }
+
+  #[inline]
+  pub fn shrink_to(&mut self, min_capacity: usize) {
+    if self.buckets.is_empty() {
+      return;
+    }
+
+    let capacity_hint = Self::min_buckets_for(
+      self.num_elements.max(min_capacity),
+      self.max_load_factor,
+    )
+    .max(MIN_LOOKUPS as usize);
     let new_capacity = self.build_hasher.policy.new_capacity(capacity_hint);
-    if new_capacity == self.capacity() && !self.buckets.is_empty() {
+
+    if new_capacity < self.bucket_count() {
+      self.resize(capacity_hint);
+    }
+  }
+
+  /// Rebuilds the table at the smallest bucket count the policy allows for
+  /// `len()` elements at the current load factor, whether that is smaller
+  /// or larger than the current one. Unlike `shrink_to_fit`, this always
+  /// rehashes, so it also clears tombstones.
+  pub fn resize_to_fit_exact(&mut self) {
+    if self.buckets.is_empty() {
       return;
     }
 
-    let new_max_lookups = Self::compute_max_lookups(new_capacity);
-    let required_vec_size =
-      Self::calculate_required_vec_len(new_capacity, new_max_lookups);
+    let capacity_hint =
+      Self::min_buckets_for(self.num_elements, self.max_load_factor)
+        .max(MIN_LOOKUPS as usize);
+    let new_capacity = self.build_hasher.policy.new_capacity(capacity_hint);
+    if let Err(err) = self.try_rehash(new_capacity) {
+      panic!("{}", err);
+    }
+  }
+
+  /// Rebuilds the table with the bucket count the policy picks for
+  /// `capacity_hint` and returns that bucket count. Does nothing if the
+  /// table already has exactly that many buckets.
+  #[inline]
+  pub fn resize(&mut self, capacity_hint: usize) -> usize {
+    match self.try_resize(capacity_hint) {
+      Ok(bucket_count) => bucket_count,
+      Err(err) => panic!("{}", err),
+    }
+  }
 
-    let new_buckets = if required_vec_size == 0 {
-      Vec::new()
-    } else {
-      let mut vec = Vec::with_capacity(required_vec_size);
-      vec.resize_with(required_vec_size, HashEntry::empty);
-      vec
-    };
+  #[inline]
+  fn try_resize(
+    &mut self,
+    capacity_hint: usize,
+  ) -> Result<usize, TryReserveError> {
+    // Keep the policy from rounding up past `usize::MAX`.
+    let max_vec_len =
+      isize::MAX as usize / core::mem::size_of::<HashEntry<(K, V)>>();
+    if capacity_hint > max_vec_len.min(MAX_CAPACITY) {
+      return Err(TryReserveError::Overflow);
+    }
+
+    let new_capacity = self.build_hasher.policy.new_capacity(capacity_hint);
+    if new_capacity == self.current_bucket_count && !self.buckets.is_empty() {
+      return Ok(new_capacity);
+    }
+
+    self.try_rehash(new_capacity)
+  }
+
+  // Moves every live entry into a fresh bucket array of `new_capacity`
+  // buckets, dropping tombstones on the way.
+  fn try_rehash(
+    &mut self,
+    new_capacity: usize,
+  ) -> Result<usize, TryReserveError> {
+    let new_buckets = Self::try_alloc_buckets(new_capacity)?;
+    let old_buckets = core::mem::replace(&mut self.buckets, new_buckets);
+    Ok(self.refill(new_capacity, old_buckets))
+  }
 
-    let old_buckets = std::mem::replace(&mut self.buckets, new_buckets);
+  // An all-empty bucket array for `new_capacity` buckets.
+  fn try_alloc_buckets(
+    new_capacity: usize,
+  ) -> Result<Vec<HashEntry<(K, V)>>, TryReserveError> {
+    let max_vec_len =
+      isize::MAX as usize / core::mem::size_of::<HashEntry<(K, V)>>();
+    let required_vec_size = Self::calculate_required_vec_len(new_capacity);
+    if required_vec_size > max_vec_len {
+      return Err(TryReserveError::Overflow);
+    }
+
+    let mut new_buckets = Vec::new();
+    new_buckets
+      .try_reserve_exact(required_vec_size)
+      .map_err(|_| TryReserveError::AllocError)?;
+    new_buckets.resize_with(required_vec_size, HashEntry::empty);
+    Ok(new_buckets)
+  }
+
+  // Second half of a rehash, once `self.buckets` holds the fresh array for
+  // `new_capacity` buckets: resets the bookkeeping for that size and places
+  // the `num_elements` live entries found in `old_buckets`.
+  fn refill(
+    &mut self,
+    new_capacity: usize,
+    old_buckets: Vec<HashEntry<(K, V)>>,
+  ) -> usize {
+    let new_max_lookups =
+      Self::compute_max_lookups(new_capacity, self.max_load_factor);
     let _old_num_slots =
-      std::mem::replace(&mut self.num_slots, new_capacity.saturating_sub(1));
+      core::mem::replace(&mut self.num_slots, new_capacity.saturating_sub(1));
+    let old_bucket_count =
+      core::mem::replace(&mut self.current_bucket_count, new_capacity);
     let _old_max_loockups =
-      std::mem::replace(&mut self.max_lookups, new_max_lookups);
-    let old_num_elements = std::mem::replace(&mut self.num_elements, 0);
+      core::mem::replace(&mut self.max_lookups, new_max_lookups);
+    let old_num_elements = core::mem::replace(&mut self.num_elements, 0);
+    self.num_tombstones = 0;
+    self.removals_since_resize = 0;
+
+    self.build_hasher.policy.reset();
+    self.build_hasher.policy.commit(new_capacity as u64);
 
     if old_num_elements > 0 {
       for mut entry in old_buckets {
@@ -382,20 +1394,96 @@ where
             .value
             .take()
             .expect("Value existed, but Option was None during resize");
-          self.insert_during_resize(key, value);
+          self.insert_during_resize(entry.hash, key, value);
         }
       }
     }
+
+    #[cfg(debug_assertions)]
+    self.debug_validate();
+
+    if old_bucket_count != new_capacity
+      && let Some(hook) = self.resize_hook.as_mut()
+    {
+      hook(old_bucket_count, new_capacity);
+    }
+
+    self.current_bucket_count
+  }
+
+  // Walks the whole bucket array and checks that every stored distance and
+  // the element count agree with the layout. A mismatch here means lookups
+  // would stop early and miss keys that are present. Keys are never
+  // rehashed, so this trusts the cached hashes and stays invisible to
+  // hashers that count their calls.
+  #[cfg(debug_assertions)]
+  fn debug_validate(&self) {
+    let mut occupied = 0;
+    let mut tombstones = 0;
+    // Insertions step over tombstones, so the entry after one may sit
+    // further from home than the usual ordering allows.
+    let mut previous_distance = None;
+    for (index, entry) in self.buckets.iter().enumerate() {
+      if entry.is_tombstone() {
+        tombstones += 1;
+        previous_distance = None;
+        continue;
+      }
+      if entry.value.is_none() {
+        assert_eq!(
+          entry.desired_distance, -1,
+          "empty slot {} has distance {}",
+          index, entry.desired_distance
+        );
+        previous_distance = Some(-1);
+        continue;
+      }
+      occupied += 1;
+
+      let ideal = self
+        .build_hasher
+        .policy
+        .hash_index(entry.hash, self.num_slots);
+      let displacement = if index >= ideal {
+        index - ideal
+      } else {
+        index + self.buckets.len() - ideal
+      };
+      assert_eq!(
+        entry.desired_distance as usize, displacement,
+        "slot {} is {} away from its ideal bucket {} but records {}",
+        index, displacement, ideal, entry.desired_distance
+      );
+      assert!(
+        entry.desired_distance <= self.max_lookups,
+        "slot {} has distance {} past max_lookups {}",
+        index,
+        entry.desired_distance,
+        self.max_lookups
+      );
+      if let Some(previous) = previous_distance {
+        assert!(
+          entry.desired_distance <= previous + 1,
+          "slot {} breaks the Robin Hood ordering",
+          index
+        );
+      }
+      previous_distance = Some(entry.desired_distance);
+    }
+    assert_eq!(occupied, self.num_elements, "element count out of sync");
+    assert_eq!(
+      tombstones, self.num_tombstones,
+      "tombstone count out of sync"
+    );
   }
 
   #[inline]
-  fn insert_during_resize(&mut self, key: K, value: V) {
-    let hash = self.hash_key(&key);
+  fn insert_during_resize(&mut self, mut hash: u64, key: K, value: V) {
     let desired_index =
       self.build_hasher.policy.hash_index(hash, self.num_slots);
 
     let mut current_index = desired_index;
-    let mut distance = 0i8;
+    let mut distance = 0i32;
     let mut item_to_insert = Some((key, value));
 
     if self.buckets.is_empty() {
@@ -416,13 +1504,15 @@ where
       if entry.is_empty() {
         entry.value = item_to_insert.take();
         entry.desired_distance = distance;
+        entry.hash = hash;
         self.num_elements += 1;
         return;
       }
 
       if entry.desired_distance < distance {
-        std::mem::swap(&mut item_to_insert, &mut entry.value);
-        std::mem::swap(&mut distance, &mut entry.desired_distance);
+        core::mem::swap(&mut item_to_insert, &mut entry.value);
+        core::mem::swap(&mut distance, &mut entry.desired_distance);
+        core::mem::swap(&mut hash, &mut entry.hash);
       }
 
       distance += 1;
@@ -435,137 +1525,526 @@ where
 
   #[inline]
   pub fn insert(&mut self, key: K, value: V) -> Option<V> {
-    self.reserve(1);
+    match self.entry(key) {
+      Entry::Occupied(entry) => {
+        Some(core::mem::replace(entry.into_mut(), value))
+      }
+      Entry::Vacant(entry) => {
+        entry.insert(value);
+        None
+      }
+    }
+  }
 
-    let mut item_to_insert = Some((key, value));
+  /// Stores `value` under `key`, always overwriting, and returns the value
+  /// it replaced. Behaves exactly like `insert`.
+  #[inline]
+  pub fn replace(&mut self, key: K, value: V) -> Option<V> {
+    self.insert(key, value)
+  }
 
-    'insert_loop: loop {
-      if self.buckets.is_empty() {
-        self.resize(MIN_LOOKUPS as usize);
-        if self.buckets.is_empty() {
-          panic!("resize failed");
+  /// Inserts `value` if `key` is absent, or overwrites the existing value
+  /// only when `pred` accepts it. Returns the replaced value; `None` means
+  /// the key was new or the existing value was kept and `value` dropped.
+  #[inline]
+  pub fn get_or_replace_if<F>(
+    &mut self,
+    key: K,
+    value: V,
+    mut pred: F,
+  ) -> Option<V>
+  where
+    F: FnMut(&V) -> bool,
+  {
+    match self.entry(key) {
+      Entry::Occupied(mut entry) => {
+        let existing = entry.get_mut();
+        if pred(existing) {
+          Some(core::mem::replace(existing, value))
+        } else {
+          None
         }
-        continue 'insert_loop;
       }
+      Entry::Vacant(entry) => {
+        entry.insert(value);
+        None
+      }
+    }
+  }
 
-      let current_key_ref = match &item_to_insert {
-        Some((k, _)) => k,
-        None => panic!("None unexpectidly in insert"),
-      };
-
-      let hash = self.hash_key(current_key_ref);
-      let desired_index =
-        self.build_hasher.policy.hash_index(hash, self.num_slots);
-
-      let mut current_index = desired_index;
-      let mut distance = 0i8;
-
-      loop {
-        if distance > self.max_lookups {
-          let (k_to_reinsert, v_to_reinsert) = item_to_insert
-            .take()
-            .expect("item cannot be None for resize");
+  #[inline]
+  pub fn get_or_insert(&mut self, key: K, default: V) -> &mut V {
+    self.entry(key).or_insert(default)
+  }
 
-          self.resize(self.num_slots + 1);
+  #[inline]
+  pub fn get_or_insert_with<F: FnOnce() -> V>(
+    &mut self,
+    key: K,
+    f: F,
+  ) -> &mut V {
+    self.entry(key).or_insert_with(f)
+  }
 
-          item_to_insert = Some((k_to_reinsert, v_to_reinsert));
-          continue 'insert_loop;
-        }
+  #[inline]
+  pub fn entry_or_default(&mut self, key: K) -> &mut V
+  where
+    V: Default,
+  {
+    self.entry(key).or_default()
+  }
 
-        if current_index >= self.buckets.len() {
-          current_index = 0;
-        }
+  /// Applies `modify` to the value under `key`, or inserts `default()` if
+  /// there is none, and returns the value either way. Same as
+  /// `entry(key).and_modify(modify).or_insert_with(default)`.
+  pub fn modify_or_insert<M, D>(
+    &mut self,
+    key: K,
+    modify: M,
+    default: D,
+  ) -> &mut V
+  where
+    M: FnOnce(&mut V),
+    D: FnOnce() -> V,
+  {
+    let (hash, probe) = self.probe_for_insert(&key);
+    match probe {
+      Probe::Found(index) => {
+        let value = self.value_mut_at(index);
+        modify(value);
+        value
+      }
+      Probe::Vacant { index, distance } => {
+        let index = self.place_new(index, distance, hash, key, default());
+        self.value_mut_at(index)
+      }
+    }
+  }
 
-        debug_assert!(
-          current_index < self.buckets.len(),
-          "current_index out of bounds in insert ({}/{})",
-          current_index,
-          self.buckets.len()
-        );
+  /// Inserts `value` if `key` is absent and returns `true`. Otherwise drops
+  /// `value`, calls `on_existing` on the stored value and returns `false`.
+  pub fn insert_or_update<F>(
+    &mut self,
+    key: K,
+    value: V,
+    on_existing: F,
+  ) -> bool
+  where
+    F: FnOnce(&mut V),
+  {
+    let (hash, probe) = self.probe_for_insert(&key);
+    match probe {
+      Probe::Found(index) => {
+        on_existing(self.value_mut_at(index));
+        false
+      }
+      Probe::Vacant { index, distance } => {
+        self.place_new(index, distance, hash, key, value);
+        true
+      }
+    }
+  }
 
-        let entry = &mut self.buckets[current_index];
+  #[inline]
+  pub fn entry(&mut self, key: K) -> Entry<'_, K, V, H, P> {
+    let (hash, probe) = self.probe_for_insert(&key);
+    match probe {
+      Probe::Found(index) => {
+        Entry::Occupied(OccupiedEntry { table: self, index })
+      }
+      Probe::Vacant { index, distance } => Entry::Vacant(VacantEntry {
+        table: self,
+        key,
+        hash,
+        index,
+        distance,
+      }),
+    }
+  }
 
-        if let Some((entry_key, entry_value)) = entry.value.as_mut() {
-          if let Some((key_to_compare, _)) = &item_to_insert {
-            if key_to_compare == entry_key {
-              let (_, new_value) = item_to_insert.take().unwrap();
-              let old_val = std::mem::replace(entry_value, new_value);
-              return Some(old_val);
-            }
-          }
-        }
-        if entry.is_empty() {
-          entry.value = item_to_insert.take();
-          entry.desired_distance = distance;
-          self.num_elements += 1;
-          return None;
-        }
+  #[inline]
+  pub fn entry_ref<'a, Q>(
+    &mut self,
+    key: &'a Q,
+  ) -> EntryRef<'a, '_, K, V, Q, H, P>
+  where
+    K: Borrow<Q> + From<&'a Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let (hash, probe) = self.probe_for_insert(key);
+    match probe {
+      Probe::Found(index) => {
+        EntryRef::Occupied(OccupiedEntry { table: self, index })
+      }
+      Probe::Vacant { index, distance } => EntryRef::Vacant(VacantEntryRef {
+        table: self,
+        key,
+        hash,
+        index,
+        distance,
+      }),
+    }
+  }
 
-        if entry.desired_distance < distance {
-          std::mem::swap(&mut item_to_insert, &mut entry.value);
-          std::mem::swap(&mut distance, &mut entry.desired_distance);
-        }
+  /// Returns the value under `key`, first inserting `make_value()` under
+  /// an owned copy of `key` if there is none. The owned key is only built
+  /// on a miss, so hits never allocate, which suits interning `&str`s into
+  /// a `HashTable<String, _>`.
+  pub fn get_or_insert_ref<'a, Q, F>(&mut self, key: &'a Q, make_value: F) -> &V
+  where
+    K: Borrow<Q> + From<&'a Q>,
+    Q: Hash + Eq + ?Sized,
+    F: FnOnce() -> V,
+  {
+    let (hash, probe) = self.probe_for_insert(key);
+    let index = match probe {
+      Probe::Found(index) => index,
+      Probe::Vacant { index, distance } => {
+        self.place_new(index, distance, hash, K::from(key), make_value())
+      }
+    };
+    self.value_mut_at(index)
+  }
 
-        distance += 1;
-        current_index += 1;
-        if current_index == self.buckets.len() {
-          current_index = 0;
-        }
+  #[inline]
+  pub fn try_insert(
+    &mut self,
+    key: K,
+    value: V,
+  ) -> Result<&mut V, OccupiedError<'_, K, V>> {
+    let (hash, probe) = self.probe_for_insert(&key);
+    match probe {
+      Probe::Found(index) => Err(OccupiedError {
+        key,
+        value,
+        entry_ref: self.value_mut_at(index),
+      }),
+      Probe::Vacant { index, distance } => {
+        let index = self.place_new(index, distance, hash, key, value);
+        Ok(self.value_mut_at(index))
       }
     }
   }
 
+  /// Inserts a pair without checking whether the key is already present,
+  /// skipping every key comparison on the way to the free slot.
+  ///
+  /// Inserting a key that is already in the table is a logic error: the
+  /// table ends up with two entries for it and which one lookups find is
+  /// unspecified. Memory safety is not affected.
+  #[inline]
+  pub fn insert_unique_unchecked(&mut self, key: K, value: V) -> &mut V {
+    self.reserve(1);
+    let hash = self.hash_key(&key);
+    let index = self.insert_absent(hash, key, value);
+    self.value_mut_at(index)
+  }
+
   #[inline]
-  pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&V>
+  fn probe_for_insert<Q>(&mut self, key: &Q) -> (u64, Probe)
   where
     K: Borrow<Q>,
-    Q: Hash + Eq,
+    Q: Hash + Eq + ?Sized,
   {
-    if self.is_empty() || self.buckets.is_empty() {
-      return None;
+    if self.buckets.is_empty() {
+      self.resize(MIN_LOOKUPS as usize);
     }
 
-    let desired_index = self.desired_index(key);
-    let mut current_index = desired_index;
-    let mut distance = 0i8;
+    let hash = self.hash_key(key);
+    let probe = self.probe(hash, |entry_key| entry_key.borrow() == key);
+    if let Probe::Found(_) = probe {
+      return (hash, probe);
+    }
+
+    // Only make room once the key turns out to be missing, so updating a
+    // present key never resizes. A rehash allocates the new buckets while
+    // the old ones are still alive, so a moved pointer means the slot found
+    // above is stale.
+    let buckets = self.buckets.as_ptr();
+    self.reserve(1);
+    if self.buckets.as_ptr() == buckets {
+      return (hash, probe);
+    }
+    (
+      hash,
+      self.probe(hash, |entry_key| entry_key.borrow() == key),
+    )
+  }
+
+  #[inline]
+  pub(crate) fn value_mut_at(&mut self, index: usize) -> &mut V {
+    &mut self.buckets[index]
+      .value
+      .as_mut()
+      .expect("expected an occupied slot")
+      .1
+  }
+
+  #[inline]
+  fn probe<F>(&self, hash: u64, mut eq: F) -> Probe
+  where
+    F: FnMut(&K) -> bool,
+  {
+    debug_assert!(!self.buckets.is_empty(), "probe on unallocated table");
+
+    let mut current_index =
+      self.build_hasher.policy.hash_index(hash, self.num_slots);
+    let mut distance = 0i32;
 
     loop {
+      if distance > self.max_lookups {
+        return Probe::Vacant {
+          index: current_index,
+          distance,
+        };
+      }
+
       if current_index >= self.buckets.len() {
         current_index = 0;
       }
 
       let entry = &self.buckets[current_index];
 
-      if entry.has_value() && entry.desired_distance < distance {
-        return None;
-      }
-
-      if let Some((entry_key, entry_value)) = entry.value.as_ref() {
-        if self.keys_equal(key, entry_key) {
-          return Some(entry_value);
-        }
+      if entry.is_empty()
+        || (entry.has_value() && entry.desired_distance < distance)
+        || self.tombstone_is_reusable(current_index)
+      {
+        return Probe::Vacant {
+          index: current_index,
+          distance,
+        };
       }
 
-      if entry.is_empty() {
-        return None;
-      }
-      if distance >= self.max_lookups {
-        return None;
+      if let Some((entry_key, _)) = entry.value.as_ref()
+        && eq(entry_key)
+      {
+        return Probe::Found(current_index);
       }
 
       distance += 1;
       current_index += 1;
-      if current_index == self.buckets.len() {
-        current_index = 0;
-      }
     }
   }
 
+  // A tombstone can be filled by whatever reaches it first only if no key
+  // stored further along probes through it: the next slot is empty or
+  // holds an entry in its ideal bucket. Anywhere else, a later key may
+  // have walked past it at a greater distance than the newcomer's and
+  // would then stop short at it.
   #[inline]
-  pub fn get_mut<Q: ?Sized>(&mut self, key: &Q) -> Option<&mut V>
-  where
-    K: Borrow<Q>,
-    Q: Hash + Eq,
+  fn tombstone_is_reusable(&self, index: usize) -> bool {
+    if !self.buckets[index].is_tombstone() {
+      return false;
+    }
+    let next = &self.buckets[(index + 1) % self.buckets.len()];
+    next.is_empty() || next.is_at_desired_position()
+  }
+
+  #[inline]
+  pub(crate) fn insert_absent(&mut self, hash: u64, key: K, value: V) -> usize {
+    if self.buckets.is_empty() {
+      self.resize(MIN_LOOKUPS as usize);
+    }
+
+    match self.probe(hash, |_| false) {
+      Probe::Vacant { index, distance } => {
+        self.place_new(index, distance, hash, key, value)
+      }
+      Probe::Found(_) => unreachable!("probe matched with a false predicate"),
+    }
+  }
+
+  // Makes room once a probe has run past `max_lookups`. Tombstones are the
+  // usual cause, so the first attempt just rebuilds at the same size; only
+  // if the retried probe overflows again does the policy get asked for
+  // the next size up. A policy that hands back the same or a smaller
+  // bucket count would otherwise send `place_new` round the same overflow
+  // forever.
+  #[cold]
+  fn make_room_after_probe_overflow(&mut self) {
+    if self.num_tombstones > 0 {
+      if let Err(err) = self.try_rehash(self.current_bucket_count) {
+        panic!("{}", err);
+      }
+      return;
+    }
+
+    let old_bucket_count = self.current_bucket_count;
+    let hint = old_bucket_count.saturating_add(1);
+    let new_bucket_count = self.build_hasher.policy.new_capacity(hint);
+    if new_bucket_count <= old_bucket_count {
+      panic!(
+        "hash policy did not grow the table past {} buckets after a probe \
+         overflow; got {}",
+        old_bucket_count, new_bucket_count
+      );
+    }
+    self.resize(hint);
+  }
+
+  // Robin Hood placement of a pair known to be absent, starting at the
+  // vacant position found by `probe`. Returns the slot the new pair ends
+  // up in, which stays put while poorer entries are pushed further along.
+  #[inline]
+  fn place_new(
+    &mut self,
+    mut current_index: usize,
+    mut distance: i32,
+    mut hash: u64,
+    key: K,
+    value: V,
+  ) -> usize {
+    let mut item_to_insert = Some((key, value));
+    let mut placed_at: Option<usize> = None;
+
+    loop {
+      if distance > self.max_lookups {
+        let displaced = item_to_insert
+          .take()
+          .expect("item cannot be None for resize");
+
+        let Some(new_index) = placed_at else {
+          self.make_room_after_probe_overflow();
+          let (key, value) = displaced;
+          return self.insert_absent(hash, key, value);
+        };
+
+        let (new_key, new_value) = self.buckets[new_index]
+          .value
+          .take()
+          .expect("placed entry vanished before resize");
+        self.buckets[new_index].desired_distance = -1;
+        let new_hash = self.buckets[new_index].hash;
+
+        self.make_room_after_probe_overflow();
+        let (key, value) = displaced;
+        self.insert_during_resize(hash, key, value);
+        return self.insert_absent(new_hash, new_key, new_value);
+      }
+
+      if current_index >= self.buckets.len() {
+        current_index = 0;
+      }
+
+      if self.tombstone_is_reusable(current_index) {
+        self.buckets[current_index] = HashEntry::empty();
+        self.num_tombstones -= 1;
+      }
+
+      let entry = &mut self.buckets[current_index];
+
+      if entry.is_empty() {
+        entry.value = item_to_insert.take();
+        entry.desired_distance = distance;
+        entry.hash = hash;
+        self.num_elements += 1;
+        return placed_at.unwrap_or(current_index);
+      }
+
+      if entry.has_value() && entry.desired_distance < distance {
+        core::mem::swap(&mut item_to_insert, &mut entry.value);
+        core::mem::swap(&mut distance, &mut entry.desired_distance);
+        core::mem::swap(&mut hash, &mut entry.hash);
+        placed_at.get_or_insert(current_index);
+      }
+
+      distance += 1;
+      current_index += 1;
+    }
+  }
+
+  /// Looks up an entry by a hash computed ahead of time with this table's
+  /// `hasher()`, using `eq` instead of `Eq` to recognise the key. A hash
+  /// from any other hasher simply won't find the entry.
+  #[inline]
+  pub fn get_by_hash<F>(&self, hash: u64, eq: F) -> Option<&V>
+  where
+    F: FnMut(&K) -> bool,
+  {
+    if self.is_empty() || self.buckets.is_empty() {
+      return None;
+    }
+
+    match self.probe(hash, eq) {
+      Probe::Found(index) => self.buckets[index].value.as_ref().map(|(_, v)| v),
+      Probe::Vacant { .. } => None,
+    }
+  }
+
+  /// Finds the entry whose key satisfies `pred`, for lookups `Borrow`
+  /// can't express, such as matching on one field of a composite key.
+  /// `hash` must be what this table's `hasher()` produces for the stored
+  /// key, so the key's `Hash` impl has to cover only what the caller can
+  /// reconstruct. With any other hash the entry is simply not found. Same
+  /// as `get_by_hash`.
+  #[inline]
+  pub fn get_by<F>(&self, hash: u64, pred: F) -> Option<&V>
+  where
+    F: Fn(&K) -> bool,
+  {
+    self.get_by_hash(hash, pred)
+  }
+
+  /// Like `get` for a key known to be present, skipping the checks that
+  /// only matter when it is missing.
+  ///
+  /// # Safety
+  ///
+  /// `key` must be in the table. Looking up a missing key is undefined
+  /// behavior.
+  #[inline]
+  pub unsafe fn get_unchecked<Q>(&self, key: &Q) -> &V
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    debug_assert!(self.contains_key(key), "get_unchecked on a missing key");
+
+    let mut current_index = self.desired_index(key);
+    loop {
+      if current_index >= self.buckets.len() {
+        current_index = 0;
+      }
+
+      // SAFETY: the index was just wrapped into bounds, and the caller
+      // guarantees the key is stored somewhere along this probe, so the
+      // loop ends at it before running into an empty slot.
+      let entry = unsafe { self.buckets.get_unchecked(current_index) };
+      if let Some((entry_key, entry_value)) = entry.value.as_ref()
+        && self.keys_equal(key, entry_key)
+      {
+        return entry_value;
+      }
+
+      current_index += 1;
+    }
+  }
+
+  // Pulls the bucket at `index` towards the cache ahead of the probe
+  // reaching it. Past the end there is nothing to fetch, and the wrap back
+  // to bucket 0 is rare enough not to bother.
+  #[inline(always)]
+  fn prefetch_bucket(&self, index: usize) {
+    #[cfg(all(feature = "prefetch", target_arch = "x86_64"))]
+    if let Some(entry) = self.buckets.get(index) {
+      use core::arch::x86_64::_mm_prefetch;
+      use core::arch::x86_64::_MM_HINT_T0;
+
+      // SAFETY: SSE is part of the x86_64 baseline, and prefetching never
+      // faults, whatever the address.
+      unsafe {
+        _mm_prefetch::<_MM_HINT_T0>((entry as *const HashEntry<(K, V)>).cast())
+      };
+    }
+    #[cfg(not(all(feature = "prefetch", target_arch = "x86_64")))]
+    let _ = index;
+  }
+
+  #[inline]
+  pub fn get<Q>(&self, key: &Q) -> Option<&V>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
   {
     if self.is_empty() || self.buckets.is_empty() {
       return None;
@@ -573,29 +2052,29 @@ where
 
     let desired_index = self.desired_index(key);
     let mut current_index = desired_index;
-    let mut distance = 0i8;
+    let mut distance = 0i32;
 
     loop {
       if current_index >= self.buckets.len() {
         current_index = 0;
       }
 
+      self.prefetch_bucket(current_index + 1);
       let entry = &self.buckets[current_index];
 
       if entry.has_value() && entry.desired_distance < distance {
         return None;
       }
 
-      if let Some((entry_key, _)) = &entry.value {
-        if self.keys_equal(key, entry_key) {
-          return self.buckets[current_index].value.as_mut().map(|(_, v)| v);
-        }
+      if let Some((entry_key, entry_value)) = entry.value.as_ref()
+        && self.keys_equal(key, entry_key)
+      {
+        return Some(entry_value);
       }
 
       if entry.is_empty() {
         return None;
       }
-
       if distance >= self.max_lookups {
         return None;
       }
@@ -609,142 +2088,1410 @@ where
   }
 
   #[inline]
-  pub fn hasher(&self) -> &H {
-    &self.build_hasher.build_hasher
-  }
-
-  #[inline]
-  pub fn policy(&self) -> &P {
-    &self.build_hasher.policy
-  }
-
-  pub fn iter(&self) -> Iter<'_, K, V> {
-    Iter {
-      buckets: &self.buckets,
-      index: 0,
-      items_remaining: self.num_elements,
-    }
-  }
-
-  #[inline]
-  pub fn remove<Q: ?Sized>(&mut self, key: &Q) -> Option<V>
+  pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
   where
     K: Borrow<Q>,
-    Q: Hash + Eq,
+    Q: Hash + Eq + ?Sized,
   {
     if self.is_empty() || self.buckets.is_empty() {
       return None;
     }
 
-    let desired_idx_of_key_to_remove = self.desired_index(key);
-    let mut current_probe_idx = desired_idx_of_key_to_remove;
-    let mut distance = 0i8;
+    let desired_index = self.desired_index(key);
+    let mut current_index = desired_index;
+    let mut distance = 0i32;
 
     loop {
-      if current_probe_idx >= self.buckets.len() {
-        current_probe_idx = 0;
+      if current_index >= self.buckets.len() {
+        current_index = 0;
       }
 
-      let entry = &self.buckets[current_probe_idx];
+      self.prefetch_bucket(current_index + 1);
+      let entry = &self.buckets[current_index];
 
-      if entry.is_empty() || entry.desired_distance < distance {
+      if entry.has_value() && entry.desired_distance < distance {
         return None;
       }
 
-      if let Some((entry_key, _)) = entry.value.as_ref() {
-        if self.keys_equal(key, entry_key) {
-          break;
-        }
+      if let Some((entry_key, _)) = &entry.value
+        && self.keys_equal(key, entry_key)
+      {
+        return self.buckets[current_index].value.as_mut().map(|(_, v)| v);
       }
 
-      distance += 1;
-      if distance > self.max_lookups {
+      if entry.is_empty() {
         return None;
       }
-      current_probe_idx += 1;
-      if current_probe_idx == self.buckets.len() {
-        current_probe_idx = 0;
+
+      if distance >= self.max_lookups {
+        return None;
+      }
+
+      distance += 1;
+      current_index += 1;
+      if current_index == self.buckets.len() {
+        current_index = 0;
       }
     }
+  }
 
-    let mut hole_idx = current_probe_idx;
+  pub fn get_many<Q, const N: usize>(&self, keys: [&Q; N]) -> [Option<&V>; N]
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    keys.map(|key| self.get(key))
+  }
 
-    let removed_value = self.buckets[hole_idx].value.take().unwrap().1;
-    self.buckets[hole_idx].desired_distance = -1;
-    self.num_elements -= 1;
+  pub fn get_many_mut<Q, const N: usize>(
+    &mut self,
+    keys: [&Q; N],
+  ) -> Option<[&mut V; N]>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let mut indices = [0usize; N];
 
-    loop {
-      let mut candidate_to_shift_idx = hole_idx + 1;
-      if candidate_to_shift_idx == self.buckets.len() {
-        candidate_to_shift_idx = 0;
-      }
+    for (i, key) in keys.into_iter().enumerate() {
+      let index = self.index_of(key)?;
 
-      if self.buckets[candidate_to_shift_idx].is_empty()
-        || self.buckets[candidate_to_shift_idx].desired_distance == 0
-      {
-        break;
+      // Distinct keys always resolve to distinct slots, but comparing the
+      // slots themselves is what makes handing out the references sound.
+      if indices[..i].contains(&index) {
+        return None;
       }
+      indices[i] = index;
+    }
 
-      let value_to_move = self.buckets[candidate_to_shift_idx].value.take();
-      let dd_of_moved_item =
-        self.buckets[candidate_to_shift_idx].desired_distance;
+    let buckets = self.buckets.as_mut_ptr();
+    Some(indices.map(|index| {
+      // SAFETY: every index came from a successful probe, so it is in bounds
+      // and occupied, and the check above guarantees no two are equal.
+      let entry = unsafe { &mut *buckets.add(index) };
+      &mut entry.value.as_mut().expect("expected an occupied slot").1
+    }))
+  }
 
-      self.buckets[hole_idx].value = value_to_move;
-      self.buckets[hole_idx].desired_distance = dd_of_moved_item - 1;
+  /// Applies `f` to the value stored under `key`, if any, and reports
+  /// whether it ran.
+  pub fn update_with<Q, F>(&mut self, key: &Q, f: F) -> bool
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    F: FnOnce(&mut V),
+  {
+    match self.index_of(key) {
+      Some(index) => {
+        f(self.value_mut_at(index));
+        true
+      }
+      None => false,
+    }
+  }
 
-      self.buckets[candidate_to_shift_idx].value = None;
-      self.buckets[candidate_to_shift_idx].desired_distance = -1;
+  /// Like `update_with`, but hands back whatever `f` returns, or `None`
+  /// if `key` is absent.
+  pub fn modify<Q, R, F>(&mut self, key: &Q, f: F) -> Option<R>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    F: FnOnce(&mut V) -> R,
+  {
+    let index = self.index_of(key)?;
+    Some(f(self.value_mut_at(index)))
+  }
 
-      hole_idx = candidate_to_shift_idx;
+  /// Mutable references to the values of two keys at once. When both
+  /// keys name the same entry, only the first reference is handed out.
+  pub fn get_pair_mut<Q>(
+    &mut self,
+    a: &Q,
+    b: &Q,
+  ) -> (Option<&mut V>, Option<&mut V>)
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let index_a = self.index_of(a);
+    let index_b = self.index_of(b).filter(|&index| Some(index) != index_a);
+
+    match (index_a, index_b) {
+      (Some(index_a), Some(index_b)) => {
+        let (low, high) = (index_a.min(index_b), index_a.max(index_b));
+        let (front, back) = self.buckets.split_at_mut(high);
+        let low_value = Self::entry_value_mut(&mut front[low]);
+        let high_value = Self::entry_value_mut(&mut back[0]);
+        if index_a < index_b {
+          (Some(low_value), Some(high_value))
+        } else {
+          (Some(high_value), Some(low_value))
+        }
+      }
+      (Some(index), None) => (Some(self.value_mut_at(index)), None),
+      (None, Some(index)) => (None, Some(self.value_mut_at(index))),
+      (None, None) => (None, None),
     }
+  }
 
-    Some(removed_value)
+  #[inline]
+  fn index_of<Q>(&self, key: &Q) -> Option<usize>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self
+      .index_by_hash(self.hash_key(key), |entry_key| entry_key.borrow() == key)
   }
-}
 
-pub struct Iter<'a, K, V> {
-  buckets: &'a [HashEntry<(K, V)>],
-  index: usize,
-  items_remaining: usize,
-}
+  #[inline]
+  pub(crate) fn index_by_hash<F>(&self, hash: u64, eq: F) -> Option<usize>
+  where
+    F: FnMut(&K) -> bool,
+  {
+    if self.is_empty() || self.buckets.is_empty() {
+      return None;
+    }
 
-impl<'a, K, V> Iterator for Iter<'a, K, V> {
-  type Item = (&'a K, &'a V);
+    match self.probe(hash, eq) {
+      Probe::Found(index) => Some(index),
+      Probe::Vacant { .. } => None,
+    }
+  }
 
-  fn next(&mut self) -> Option<Self::Item> {
-    if self.items_remaining == 0 {
-      return None;
+  #[inline]
+  fn entry_value_mut(entry: &mut HashEntry<(K, V)>) -> &mut V {
+    &mut entry.value.as_mut().expect("expected an occupied slot").1
+  }
+
+  #[inline]
+  pub fn contains_key<Q>(&self, key: &Q) -> bool
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    if self.is_empty() || self.buckets.is_empty() {
+      return false;
     }
 
-    while self.index < self.buckets.len() {
-      let entry = &self.buckets[self.index];
-      self.index += 1;
+    let desired_index = self.desired_index(key);
+    let mut current_index = desired_index;
+    let mut distance = 0i32;
 
-      if entry.has_value() {
-        if let Some((key, value)) = &entry.value {
-          self.items_remaining -= 1;
-          return Some((key, value));
-        }
+    loop {
+      if current_index >= self.buckets.len() {
+        current_index = 0;
+      }
+
+      let entry = &self.buckets[current_index];
+
+      if entry.has_value() && entry.desired_distance < distance {
+        return false;
+      }
+
+      if let Some((entry_key, _)) = entry.value.as_ref()
+        && self.keys_equal(key, entry_key)
+      {
+        return true;
+      }
+
+      if entry.is_empty() {
+        return false;
+      }
+      if distance >= self.max_lookups {
+        return false;
+      }
+
+      distance += 1;
+      current_index += 1;
+      if current_index == self.buckets.len() {
+        current_index = 0;
       }
     }
-    None
   }
 
-  fn size_hint(&self) -> (usize, Option<usize>) {
-    (self.items_remaining, Some(self.items_remaining))
+  /// Number of keys present in both tables, ignoring values. Iterates the
+  /// smaller table and probes the larger one.
+  pub fn key_intersection_count(&self, other: &Self) -> usize {
+    let (small, large) = if self.len() <= other.len() {
+      (self, other)
+    } else {
+      (other, self)
+    };
+    small.keys().filter(|key| large.contains_key(*key)).count()
   }
-}
 
-impl<'a, K, V, H, P> IntoIterator for &'a HashTable<K, V, H, P>
-where
-  K: Hash + Eq,
-  H: BuildHasher + Clone,
-  P: HashPolicy + Default + Clone,
-{
-  type Item = (&'a K, &'a V);
-  type IntoIter = Iter<'a, K, V>;
+  /// Number of distinct keys present in either table.
+  pub fn key_union_count(&self, other: &Self) -> usize {
+    self.len() + other.len() - self.key_intersection_count(other)
+  }
 
-  fn into_iter(self) -> Self::IntoIter {
+  /// Number of keys in `self` that are not in `other`.
+  pub fn key_difference_count(&self, other: &Self) -> usize {
+    self.len() - self.key_intersection_count(other)
+  }
+
+  /// Whether any entry holds `value`. Unlike the key lookups this scans
+  /// every entry, so it is O(n).
+  pub fn contains_value(&self, value: &V) -> bool
+  where
+    V: PartialEq,
+  {
+    self.values().any(|v| v == value)
+  }
+
+  #[inline]
+  pub fn hasher(&self) -> &H {
+    &self.build_hasher.build_hasher
+  }
+
+  #[inline]
+  pub fn policy(&self) -> &P {
+    &self.build_hasher.policy
+  }
+
+  #[cfg(feature = "rayon")]
+  #[inline]
+  pub(crate) fn buckets(&self) -> &[HashEntry<(K, V)>] {
+    &self.buckets
+  }
+
+  pub fn iter(&self) -> Iter<'_, K, V> {
+    // An empty table may still own a large bucket array; don't hand it out
+    // at all.
+    if self.num_elements == 0 {
+      return Iter {
+        buckets: &[],
+        index: 0,
+        items_remaining: 0,
+      };
+    }
+
+    Iter {
+      buckets: &self.buckets,
+      index: 0,
+      items_remaining: self.num_elements,
+    }
+  }
+
+  /// Yields every key with its distance from its ideal bucket, in storage
+  /// order. Mostly zeros means the hasher spreads keys well; long runs of
+  /// growing distances point at clustering.
+  pub fn probe_lengths(&self) -> impl Iterator<Item = (&K, i32)> + '_ {
+    self
+      .entries_with_distance()
+      .map(|(key, _, distance)| (key, distance))
+  }
+
+  /// Like `iter`, but guaranteed to walk the bucket array front to back and
+  /// to report each entry's distance from its ideal bucket. Visiting
+  /// entries in physical order is the cache-friendly way to batch work
+  /// over the whole table.
+  pub fn entries_with_distance(
+    &self,
+  ) -> impl Iterator<Item = (&K, &V, i32)> + '_ {
+    self.buckets.iter().filter_map(|entry| {
+      entry
+        .value
+        .as_ref()
+        .map(|(key, value)| (key, value, entry.desired_distance))
+    })
+  }
+
+  /// The first live entry in bucket order, without scanning further than
+  /// that. Which entry that is depends only on the layout, so it is
+  /// arbitrary but not random.
+  #[inline]
+  pub fn any_entry(&self) -> Option<(&K, &V)> {
+    self.iter().next()
+  }
+
+  /// The `n`th live entry in bucket order, the same order `iter` and
+  /// `entries_with_distance` use.
+  #[inline]
+  pub fn nth_entry(&self, n: usize) -> Option<(&K, &V)> {
+    if n >= self.num_elements {
+      return None;
+    }
+    self.iter().nth(n)
+  }
+
+  /// Average distance of the stored keys from their ideal buckets, or 0.0
+  /// for an empty table.
+  pub fn mean_probe_length(&self) -> f64 {
+    if self.is_empty() {
+      return 0.0;
+    }
+    let total: i64 = self.probe_lengths().map(|(_, d)| i64::from(d)).sum();
+    total as f64 / self.len() as f64
+  }
+
+  pub fn max_probe_length(&self) -> i32 {
+    self.probe_lengths().map(|(_, d)| d).max().unwrap_or(0)
+  }
+
+  /// Entry counts by distance from the ideal bucket: `histogram[d]` is the
+  /// number of keys stored `d` slots past home. The length is the largest
+  /// distance plus one, so an empty table gives an empty vector.
+  pub fn probe_distance_histogram(&self) -> Vec<usize> {
+    let mut histogram = Vec::new();
+    for (_, distance) in self.probe_lengths() {
+      let distance = distance as usize;
+      if distance >= histogram.len() {
+        histogram.resize(distance + 1, 0);
+      }
+      histogram[distance] += 1;
+    }
+    histogram
+  }
+
+  pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+    IterMut {
+      buckets: self.buckets.iter_mut(),
+      items_remaining: self.num_elements,
+    }
+  }
+
+  pub fn drain(&mut self) -> Drain<'_, K, V> {
+    if self.num_tombstones > 0 {
+      for entry in self.buckets.iter_mut().filter(|e| e.is_tombstone()) {
+        *entry = HashEntry::empty();
+      }
+      self.num_tombstones = 0;
+    }
+    let items_remaining = core::mem::replace(&mut self.num_elements, 0);
+    Drain {
+      buckets: self.buckets.iter_mut(),
+      items_remaining,
+    }
+  }
+
+  /// Moves every entry out of `other` into `self`, leaving `other` empty.
+  /// Keys present in both tables end up with the value from `other`.
+  pub fn append(&mut self, other: &mut HashTable<K, V, H, P>) {
+    self.reserve(other.len());
+    for (key, value) in other.drain() {
+      self.insert(key, value);
+    }
+  }
+
+  /// Moves every entry for which `f` returns `true` into a new table with
+  /// the same hasher, policy and load factors, and returns it. The rest
+  /// stay in `self`.
+  pub fn split_off<F>(&mut self, mut f: F) -> Self
+  where
+    F: FnMut(&K, &V) -> bool,
+  {
+    let mut split = Self::with_hasher_and_policy(
+      self.build_hasher.build_hasher.clone(),
+      self.build_hasher.policy.clone(),
+    );
+    split.set_max_load_factor(self.max_load_factor);
+    split.min_load_factor = self.min_load_factor;
+    split.shrink_ratio = self.shrink_ratio;
+
+    for (key, value) in self.extract_if(|key, value| f(key, value)) {
+      split.insert(key, value);
+    }
+    split
+  }
+
+  pub fn keys(&self) -> Keys<'_, K, V> {
+    Keys { inner: self.iter() }
+  }
+
+  pub fn values(&self) -> Values<'_, K, V> {
+    Values { inner: self.iter() }
+  }
+
+  pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+    ValuesMut {
+      inner: self.iter_mut(),
+    }
+  }
+
+  pub fn into_keys(self) -> IntoKeys<K, V> {
+    IntoKeys {
+      inner: self.into_iter(),
+    }
+  }
+
+  pub fn into_values(self) -> IntoValues<K, V> {
+    IntoValues {
+      inner: self.into_iter(),
+    }
+  }
+
+  #[inline]
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self.remove_entry(key).map(|(_, value)| value)
+  }
+
+  #[inline]
+  pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    if self.is_empty() || self.buckets.is_empty() {
+      return None;
+    }
+
+    let desired_idx_of_key_to_remove = self.desired_index(key);
+    let mut current_probe_idx = desired_idx_of_key_to_remove;
+    let mut distance = 0i32;
+
+    loop {
+      if current_probe_idx >= self.buckets.len() {
+        current_probe_idx = 0;
+      }
+
+      let entry = &self.buckets[current_probe_idx];
+
+      if entry.is_empty()
+        || (entry.has_value() && entry.desired_distance < distance)
+      {
+        return None;
+      }
+
+      if let Some((entry_key, _)) = entry.value.as_ref()
+        && self.keys_equal(key, entry_key)
+      {
+        break;
+      }
+
+      distance += 1;
+      if distance > self.max_lookups {
+        return None;
+      }
+      current_probe_idx += 1;
+      if current_probe_idx == self.buckets.len() {
+        current_probe_idx = 0;
+      }
+    }
+
+    let removed = self.remove_at(current_probe_idx);
+    self.shrink_if_sparse();
+    Some(removed)
+  }
+
+  /// Removes `key` without shifting the entries after it back, leaving a
+  /// tombstone in its slot instead. Lookups and insertions walk past
+  /// tombstones, so mixing this with `get`, `insert` or `remove` stays
+  /// correct, but every tombstone lengthens the probes that cross it until
+  /// the table is rehashed. Tombstones count towards the load factor, so
+  /// an insertion they would push past it rebuilds the table first; see
+  /// `reserve_exact`. The table is also rebuilt once tombstones fill a
+  /// quarter of the buckets. Unlike `remove`, this never shrinks the
+  /// table.
+  pub fn remove_fast<Q>(&mut self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    if self.is_empty() || self.buckets.is_empty() {
+      return None;
+    }
+
+    let hash = self.hash_key(key);
+    let Probe::Found(index) =
+      self.probe(hash, |entry_key| entry_key.borrow() == key)
+    else {
+      return None;
+    };
+
+    let (_, value) = self.buckets[index]
+      .value
+      .take()
+      .expect("expected an occupied slot");
+    self.buckets[index].desired_distance = TOMBSTONE;
+    self.buckets[index].hash = 0;
+    self.num_elements -= 1;
+    self.num_tombstones += 1;
+
+    let mut next_idx = index + 1;
+    if next_idx == self.buckets.len() {
+      next_idx = 0;
+    }
+    if self.buckets[next_idx].is_empty()
+      || self.buckets[next_idx].is_at_desired_position()
+    {
+      // Nothing behind this slot probes through it, so it and any
+      // tombstones right before it can simply be emptied.
+      self.buckets[index] = HashEntry::empty();
+      self.num_tombstones -= 1;
+      self.clear_tombstones_before(index);
+    } else if self.num_tombstones > self.bucket_count() / 4 {
+      // A table well below its load factor could otherwise collect
+      // tombstones for a long time before an insertion clears them, with
+      // every probe crossing them getting slower meanwhile.
+      self.rebuild();
+    }
+    Some(value)
+  }
+
+  // The one place the automatic resize thresholds meet. Growth happens
+  // past `max_load_factor`; automatic shrinking only below a quarter of
+  // it, and to a size that leaves the load at half of it at most. A table
+  // churning around either boundary therefore has to double or halve
+  // before it resizes again, whatever `min_load_factor` and `shrink_ratio`
+  // are set to.
+  #[inline]
+  pub(crate) fn shrink_if_sparse(&mut self) {
+    let bucket_count = self.bucket_count() as f64;
+    let max_shrink_load = bucket_count * self.max_load_factor as f64 / 4.0;
+    // `set_min_load_factor` keeps this floor below `max_shrink_load`.
+    let floor = bucket_count * self.min_load_factor as f64 / 2.0;
+    let mut shrink = (self.num_elements as f64) < floor;
+
+    let removal_limit = bucket_count * self.shrink_ratio as f64;
+    if self.shrink_ratio > 0.0
+      && self.removals_since_resize as f64 > removal_limit
+    {
+      shrink |= (self.num_elements as f64) < max_shrink_load;
+      // Whether or not the table shrinks, start counting afresh rather
+      // than rechecking on every removal.
+      self.removals_since_resize = 0;
+    }
+
+    if shrink {
+      self.shrink_to(self.num_elements.saturating_mul(2));
+    }
+  }
+
+  #[inline]
+  pub(crate) fn remove_at(&mut self, index: usize) -> (K, V) {
+    let mut hole_idx = index;
+
+    let removed = self.buckets[hole_idx].value.take().unwrap();
+    self.buckets[hole_idx].desired_distance = -1;
+    self.num_elements -= 1;
+    self.removals_since_resize += 1;
+
+    loop {
+      let mut candidate_to_shift_idx = hole_idx + 1;
+      if candidate_to_shift_idx == self.buckets.len() {
+        candidate_to_shift_idx = 0;
+      }
+
+      // With no empty slot anywhere the cluster wraps all the way round;
+      // stop before pulling the entry just shifted out of `index` back in.
+      if candidate_to_shift_idx == index
+        || self.buckets[candidate_to_shift_idx].is_empty()
+        || self.buckets[candidate_to_shift_idx].is_at_desired_position()
+      {
+        break;
+      }
+
+      // Tombstones move back with the cluster so keys behind them stay
+      // reachable.
+      if self.buckets[candidate_to_shift_idx].is_tombstone() {
+        self.buckets.swap(hole_idx, candidate_to_shift_idx);
+        hole_idx = candidate_to_shift_idx;
+        continue;
+      }
+
+      let value_to_move = self.buckets[candidate_to_shift_idx].value.take();
+      let dd_of_moved_item =
+        self.buckets[candidate_to_shift_idx].desired_distance;
+      let hash_of_moved_item = self.buckets[candidate_to_shift_idx].hash;
+
+      self.buckets[hole_idx].value = value_to_move;
+      self.buckets[hole_idx].desired_distance = dd_of_moved_item - 1;
+      self.buckets[hole_idx].hash = hash_of_moved_item;
+
+      self.buckets[candidate_to_shift_idx].value = None;
+      self.buckets[candidate_to_shift_idx].desired_distance = -1;
+
+      hole_idx = candidate_to_shift_idx;
+    }
+
+    self.clear_tombstones_before(hole_idx);
+    removed
+  }
+
+  // Nothing probes past an empty slot, so a run of tombstones right before
+  // one no longer keeps any key reachable and can be emptied.
+  #[inline]
+  fn clear_tombstones_before(&mut self, empty_idx: usize) {
+    let mut index = empty_idx;
+    while self.num_tombstones > 0 {
+      index = index.checked_sub(1).unwrap_or(self.buckets.len() - 1);
+      if !self.buckets[index].is_tombstone() {
+        break;
+      }
+      self.buckets[index] = HashEntry::empty();
+      self.num_tombstones -= 1;
+    }
+  }
+
+  // Start removal walks on an empty slot: no cluster wraps past it, so the
+  // backward shift never pulls an already-visited entry into the slot
+  // being examined.
+  #[inline]
+  fn removal_walk_start(&self) -> usize {
+    self
+      .buckets
+      .iter()
+      .position(HashEntry::is_empty)
+      .unwrap_or(0)
+  }
+
+  pub fn extract_if<F>(&mut self, f: F) -> ExtractIf<'_, K, V, H, P, F>
+  where
+    F: FnMut(&K, &mut V) -> bool,
+  {
+    ExtractIf {
+      start: self.removal_walk_start(),
+      offset: 0,
+      table: self,
+      predicate: f,
+    }
+  }
+
+  pub fn retain<F>(&mut self, mut f: F)
+  where
+    F: FnMut(&K, &mut V) -> bool,
+  {
+    if self.is_empty() {
+      return;
+    }
+
+    let len = self.buckets.len();
+    let start = self.removal_walk_start();
+
+    for offset in 0..len {
+      let index = (start + offset) % len;
+      while let Some((key, value)) = self.buckets[index].value.as_mut() {
+        if f(key, value) {
+          break;
+        }
+        self.remove_at(index);
+      }
+    }
+  }
+
+  /// `retain` followed by `shrink_to_fit`: survivors are filtered in place
+  /// and the table is only rehashed, once, if the policy would give them
+  /// fewer buckets than it has now. A predicate that keeps enough entries
+  /// therefore costs no allocation. The bucket count never grows.
+  pub fn retain_shrink<F>(&mut self, f: F)
+  where
+    F: FnMut(&K, &mut V) -> bool,
+  {
+    self.retain(f);
+    self.shrink_to_fit();
+  }
+
+  /// Same as `retain`, which already hands `f` a `&mut V`; provided under
+  /// the name std uses for the mutating variant.
+  #[inline]
+  pub fn retain_mut<F>(&mut self, f: F)
+  where
+    F: FnMut(&K, &mut V) -> bool,
+  {
+    self.retain(f);
+  }
+}
+
+#[derive(Debug)]
+pub struct OccupiedError<'a, K, V> {
+  pub key: K,
+  pub value: V,
+  pub entry_ref: &'a mut V,
+}
+
+impl<K: core::fmt::Debug, V: core::fmt::Debug> core::fmt::Display
+  for OccupiedError<'_, K, V>
+{
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    write!(
+      f,
+      "failed to insert {:?}, key {:?} already exists with value {:?}",
+      self.value, self.key, self.entry_ref,
+    )
+  }
+}
+
+impl<K: core::fmt::Debug, V: core::fmt::Debug> core::error::Error
+  for OccupiedError<'_, K, V>
+{
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryReserveError {
+  /// The requested size doesn't fit in memory addressable by a `Vec`.
+  Overflow,
+  /// The allocator failed to provide the new bucket storage.
+  AllocError,
+}
+
+impl core::fmt::Display for TryReserveError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      TryReserveError::Overflow => f.write_str("capacity overflow"),
+      TryReserveError::AllocError => f.write_str("memory allocation failed"),
+    }
+  }
+}
+
+impl core::error::Error for TryReserveError {}
+
+enum Probe {
+  Found(usize),
+  Vacant { index: usize, distance: i32 },
+}
+
+pub enum Entry<'a, K, V, H, P>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  Occupied(OccupiedEntry<'a, K, V, H, P>),
+  Vacant(VacantEntry<'a, K, V, H, P>),
+}
+
+pub struct OccupiedEntry<'a, K, V, H, P>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  table: &'a mut HashTable<K, V, H, P>,
+  index: usize,
+}
+
+pub struct VacantEntry<'a, K, V, H, P>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  table: &'a mut HashTable<K, V, H, P>,
+  key: K,
+  hash: u64,
+  index: usize,
+  distance: i32,
+}
+
+impl<'a, K, V, H, P> Entry<'a, K, V, H, P>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  /// The stored key for an occupied entry, the key that would be inserted
+  /// for a vacant one.
+  #[inline]
+  pub fn key(&self) -> &K {
+    match self {
+      Entry::Occupied(entry) => entry.key(),
+      Entry::Vacant(entry) => entry.key(),
+    }
+  }
+
+  #[inline]
+  pub fn or_insert(self, default: V) -> &'a mut V {
+    match self {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => entry.insert(default),
+    }
+  }
+
+  #[inline]
+  pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+    match self {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => entry.insert(default()),
+    }
+  }
+
+  #[inline]
+  pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+    match self {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => {
+        let value = default(&entry.key);
+        entry.insert(value)
+      }
+    }
+  }
+
+  #[inline]
+  pub fn or_default(self) -> &'a mut V
+  where
+    V: Default,
+  {
+    self.or_insert_with(V::default)
+  }
+
+  #[inline]
+  pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+    match self {
+      Entry::Occupied(mut entry) => {
+        f(entry.get_mut());
+        Entry::Occupied(entry)
+      }
+      Entry::Vacant(entry) => Entry::Vacant(entry),
+    }
+  }
+}
+
+impl<'a, K, V, H, P> OccupiedEntry<'a, K, V, H, P>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  /// The key already in the table, not the one passed to `entry`.
+  #[inline]
+  pub fn key(&self) -> &K {
+    &self.table.buckets[self.index]
+      .value
+      .as_ref()
+      .expect("expected an occupied slot")
+      .0
+  }
+
+  #[inline]
+  pub fn get(&self) -> &V {
+    &self.table.buckets[self.index]
+      .value
+      .as_ref()
+      .expect("expected an occupied slot")
+      .1
+  }
+
+  #[inline]
+  pub fn get_mut(&mut self) -> &mut V {
+    self.table.value_mut_at(self.index)
+  }
+
+  #[inline]
+  pub fn into_mut(self) -> &'a mut V {
+    self.table.value_mut_at(self.index)
+  }
+
+  /// Replaces the value in place, returning the old one.
+  #[inline]
+  pub fn insert(&mut self, value: V) -> V {
+    core::mem::replace(self.get_mut(), value)
+  }
+
+  /// Removes the entry like `HashTable::remove_entry`. The backward shift
+  /// moves other entries, which is why this consumes the entry.
+  #[inline]
+  pub fn remove_entry(self) -> (K, V) {
+    let removed = self.table.remove_at(self.index);
+    self.table.shrink_if_sparse();
+    removed
+  }
+
+  #[inline]
+  pub fn remove(self) -> V {
+    self.remove_entry().1
+  }
+}
+
+impl<'a, K, V, H, P> VacantEntry<'a, K, V, H, P>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  #[inline]
+  pub fn insert(self, value: V) -> &'a mut V {
+    let table = self.table;
+    let index =
+      table.place_new(self.index, self.distance, self.hash, self.key, value);
+    table.value_mut_at(index)
+  }
+
+  #[inline]
+  pub fn key(&self) -> &K {
+    &self.key
+  }
+
+  /// Gives back the key without inserting anything.
+  #[inline]
+  pub fn into_key(self) -> K {
+    self.key
+  }
+}
+
+pub enum EntryRef<'a, 'b, K, V, Q, H, P>
+where
+  K: Hash + Eq,
+  Q: ?Sized,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  Occupied(OccupiedEntry<'b, K, V, H, P>),
+  Vacant(VacantEntryRef<'a, 'b, K, V, Q, H, P>),
+}
+
+pub struct VacantEntryRef<'a, 'b, K, V, Q, H, P>
+where
+  K: Hash + Eq,
+  Q: ?Sized,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  table: &'b mut HashTable<K, V, H, P>,
+  key: &'a Q,
+  hash: u64,
+  index: usize,
+  distance: i32,
+}
+
+impl<'a, 'b, K, V, Q, H, P> EntryRef<'a, 'b, K, V, Q, H, P>
+where
+  K: Hash + Eq + From<&'a Q>,
+  Q: ?Sized,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  #[inline]
+  pub fn or_insert(self, default: V) -> &'b mut V {
+    match self {
+      EntryRef::Occupied(entry) => entry.into_mut(),
+      EntryRef::Vacant(entry) => entry.insert(default),
+    }
+  }
+
+  #[inline]
+  pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'b mut V {
+    match self {
+      EntryRef::Occupied(entry) => entry.into_mut(),
+      EntryRef::Vacant(entry) => entry.insert(default()),
+    }
+  }
+
+  #[inline]
+  pub fn or_default(self) -> &'b mut V
+  where
+    V: Default,
+  {
+    self.or_insert_with(V::default)
+  }
+
+  #[inline]
+  pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+    match self {
+      EntryRef::Occupied(mut entry) => {
+        f(entry.get_mut());
+        EntryRef::Occupied(entry)
+      }
+      EntryRef::Vacant(entry) => EntryRef::Vacant(entry),
+    }
+  }
+}
+
+impl<'a, 'b, K, V, Q, H, P> VacantEntryRef<'a, 'b, K, V, Q, H, P>
+where
+  K: Hash + Eq + From<&'a Q>,
+  Q: ?Sized,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  #[inline]
+  pub fn insert(self, value: V) -> &'b mut V {
+    let table = self.table;
+    let key = K::from(self.key);
+    let index =
+      table.place_new(self.index, self.distance, self.hash, key, value);
+    table.value_mut_at(index)
+  }
+}
+
+pub struct Iter<'a, K, V> {
+  buckets: &'a [HashEntry<(K, V)>],
+  index: usize,
+  items_remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+  type Item = (&'a K, &'a V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.items_remaining == 0 {
+      return None;
+    }
+
+    while self.index < self.buckets.len() {
+      let entry = &self.buckets[self.index];
+      self.index += 1;
+
+      if entry.has_value()
+        && let Some((key, value)) = &entry.value
+      {
+        self.items_remaining -= 1;
+        return Some((key, value));
+      }
+    }
+    None
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.items_remaining, Some(self.items_remaining))
+  }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {
+  fn len(&self) -> usize {
+    self.items_remaining
+  }
+}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+pub struct IterMut<'a, K, V> {
+  buckets: core::slice::IterMut<'a, HashEntry<(K, V)>>,
+  items_remaining: usize,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+  type Item = (&'a K, &'a mut V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.items_remaining == 0 {
+      return None;
+    }
+
+    for entry in self.buckets.by_ref() {
+      if entry.desired_distance >= 0
+        && let Some((key, value)) = entry.value.as_mut()
+      {
+        self.items_remaining -= 1;
+        return Some((&*key, value));
+      }
+    }
+    None
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.items_remaining, Some(self.items_remaining))
+  }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {
+  fn len(&self) -> usize {
+    self.items_remaining
+  }
+}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+pub struct Keys<'a, K, V> {
+  inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+  type Item = &'a K;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next().map(|(key, _)| key)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {
+  fn len(&self) -> usize {
+    self.inner.len()
+  }
+}
+
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+pub struct Values<'a, K, V> {
+  inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+  type Item = &'a V;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next().map(|(_, value)| value)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {
+  fn len(&self) -> usize {
+    self.inner.len()
+  }
+}
+
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+pub struct ValuesMut<'a, K, V> {
+  inner: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+  type Item = &'a mut V;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next().map(|(_, value)| value)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {
+  fn len(&self) -> usize {
+    self.inner.len()
+  }
+}
+
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+
+pub struct Drain<'a, K, V> {
+  buckets: core::slice::IterMut<'a, HashEntry<(K, V)>>,
+  items_remaining: usize,
+}
+
+impl<K, V> Iterator for Drain<'_, K, V> {
+  type Item = (K, V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.items_remaining == 0 {
+      return None;
+    }
+
+    for entry in self.buckets.by_ref() {
+      if entry.has_value() {
+        let pair = entry.value.take();
+        entry.desired_distance = -1;
+        self.items_remaining -= 1;
+        return pair;
+      }
+    }
+    None
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.items_remaining, Some(self.items_remaining))
+  }
+}
+
+impl<K, V> ExactSizeIterator for Drain<'_, K, V> {
+  fn len(&self) -> usize {
+    self.items_remaining
+  }
+}
+
+impl<K, V> FusedIterator for Drain<'_, K, V> {}
+
+impl<K, V> Drop for Drain<'_, K, V> {
+  fn drop(&mut self) {
+    self.for_each(drop);
+  }
+}
+
+impl<'a, K, V, H, P> IntoIterator for &'a HashTable<K, V, H, P>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  type Item = (&'a K, &'a V);
+  type IntoIter = Iter<'a, K, V>;
+
+  fn into_iter(self) -> Self::IntoIter {
     self.iter()
   }
 }
+
+impl<'a, K, V, H, P> IntoIterator for &'a mut HashTable<K, V, H, P>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  type Item = (&'a K, &'a mut V);
+  type IntoIter = IterMut<'a, K, V>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter_mut()
+  }
+}
+
+pub struct ExtractIf<'a, K, V, H, P, F>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+  F: FnMut(&K, &mut V) -> bool,
+{
+  table: &'a mut HashTable<K, V, H, P>,
+  start: usize,
+  offset: usize,
+  predicate: F,
+}
+
+impl<K, V, H, P, F> Iterator for ExtractIf<'_, K, V, H, P, F>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+  F: FnMut(&K, &mut V) -> bool,
+{
+  type Item = (K, V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let len = self.table.buckets.len();
+    while self.offset < len {
+      let index = (self.start + self.offset) % len;
+      if let Some((key, value)) = self.table.buckets[index].value.as_mut()
+        && (self.predicate)(key, value)
+      {
+        // The backward shift may have moved the next entry into this slot,
+        // so it is examined again before moving on.
+        return Some(self.table.remove_at(index));
+      }
+      self.offset += 1;
+    }
+    None
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (0, Some(self.table.len()))
+  }
+}
+
+impl<K, V, H, P, F> FusedIterator for ExtractIf<'_, K, V, H, P, F>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+  F: FnMut(&K, &mut V) -> bool,
+{
+}
+
+impl<K, V, H, P, F> Drop for ExtractIf<'_, K, V, H, P, F>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+  F: FnMut(&K, &mut V) -> bool,
+{
+  fn drop(&mut self) {
+    self.for_each(drop);
+  }
+}
+
+pub struct IntoIter<K, V> {
+  buckets: alloc::vec::IntoIter<HashEntry<(K, V)>>,
+  items_remaining: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+  type Item = (K, V);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.items_remaining == 0 {
+      return None;
+    }
+
+    for mut entry in self.buckets.by_ref() {
+      if entry.has_value()
+        && let Some(pair) = entry.value.take()
+      {
+        self.items_remaining -= 1;
+        return Some(pair);
+      }
+    }
+    None
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.items_remaining, Some(self.items_remaining))
+  }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {
+  fn len(&self) -> usize {
+    self.items_remaining
+  }
+}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+impl<K, V, H, P> IntoIterator for HashTable<K, V, H, P>
+where
+  K: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  type Item = (K, V);
+  type IntoIter = IntoIter<K, V>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    IntoIter {
+      buckets: self.buckets.into_iter(),
+      items_remaining: self.num_elements,
+    }
+  }
+}
+
+pub struct IntoKeys<K, V> {
+  inner: IntoIter<K, V>,
+}
+
+impl<K, V> Iterator for IntoKeys<K, V> {
+  type Item = K;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next().map(|(key, _)| key)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<K, V> ExactSizeIterator for IntoKeys<K, V> {
+  fn len(&self) -> usize {
+    self.inner.len()
+  }
+}
+
+impl<K, V> FusedIterator for IntoKeys<K, V> {}
+
+pub struct IntoValues<K, V> {
+  inner: IntoIter<K, V>,
+}
+
+impl<K, V> Iterator for IntoValues<K, V> {
+  type Item = V;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next().map(|(_, value)| value)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<K, V> ExactSizeIterator for IntoValues<K, V> {
+  fn len(&self) -> usize {
+    self.inner.len()
+  }
+}
+
+impl<K, V> FusedIterator for IntoValues<K, V> {}
@@ -1,12 +1,28 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod eq_table;
 pub mod hash_table;
+mod macros;
+#[cfg(feature = "rayon")]
+mod rayon_impl;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod sherwood_set;
 
+pub use eq_table::HashTableEq;
 pub use hash_table::*;
+#[cfg(feature = "rayon")]
+pub use rayon_impl::ParIter;
+pub use sherwood_set::SherwoodSet;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
 
   use std::collections::hash_map::RandomState;
 
+  use crate::hash_table::HashEntry;
   use crate::BuildHasherWrapper;
   use crate::HashTable;
   use crate::PowerOf2HashPolicy;
@@ -45,4 +61,53 @@ mod tests {
     assert_eq!(map.get("a"), Some(&2));
     assert_eq!(map.get("b"), Some(&3));
   }
+
+  #[test]
+  fn get_unchecked_finds_present_keys() {
+    let mut map: HashTable<u64, u64> = HashTable::new();
+    for i in 0..1000 {
+      map.insert(i, i * 3);
+    }
+
+    for i in 0..1000 {
+      // SAFETY: every key in 0..1000 was inserted above.
+      assert_eq!(unsafe { *map.get_unchecked(&i) }, i * 3);
+    }
+  }
+
+  // Test-only check, no table code involved: `(K, ())` already has exactly
+  // `K`'s layout, so unit values add nothing to a bucket.
+  #[test]
+  fn unit_values_take_no_space() {
+    use core::mem::size_of;
+
+    assert_eq!(
+      size_of::<HashEntry<(u64, ())>>(),
+      size_of::<HashEntry<u64>>()
+    );
+    assert_eq!(size_of::<HashEntry<(u8, ())>>(), size_of::<HashEntry<u8>>());
+    assert_eq!(
+      size_of::<HashEntry<(String, ())>>(),
+      size_of::<HashEntry<String>>()
+    );
+  }
+
+  #[test]
+  fn set_like_table_memory() {
+    use core::mem::size_of;
+
+    let mut set: HashTable<u64, ()> = HashTable::new();
+    for i in 0..1_000_000 {
+      set.insert(i, ());
+    }
+
+    // Laid out by hand, a slot is a distance, a cached hash and an
+    // optional key, with nothing for the value.
+    let slot = size_of::<(i32, u64, Option<u64>)>();
+    assert_eq!(set.bucket_count(), 1 << 21);
+    assert_eq!(
+      set.memory_usage(),
+      size_of::<HashTable<u64, ()>>() + ((1 << 21) + 64) * slot
+    );
+  }
 }
@@ -0,0 +1,48 @@
+/// Creates a [`HashTable`](crate::HashTable) containing the given pairs,
+/// much like `vec!` does for vectors.
+///
+/// ```
+/// use sherwood_table::hashtable;
+///
+/// let table = hashtable! { "a" => 1, "b" => 2 };
+/// assert_eq!(table.get("a"), Some(&1));
+/// assert_eq!(table.len(), 2);
+/// ```
+///
+/// Later duplicates overwrite earlier ones, as with `insert`.
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! hashtable {
+  () => {
+    $crate::HashTable::<_, _>::new()
+  };
+  ($($key:expr => $value:expr),+ $(,)?) => {{
+    let mut table = $crate::HashTable::<_, _>::new();
+    table.reserve(<[&str]>::len(&[$(stringify!($key)),+]));
+    $(
+      table.insert($key, $value);
+    )+
+    table
+  }};
+}
+
+/// Creates a [`HashTable`](crate::HashTable) containing the given pairs,
+/// much like `vec!` does for vectors. Without `std` there is no default
+/// hasher, so the table's type has to be spelled out at the call site.
+///
+/// Later duplicates overwrite earlier ones, as with `insert`.
+#[cfg(not(feature = "std"))]
+#[macro_export]
+macro_rules! hashtable {
+  () => {
+    $crate::HashTable::new()
+  };
+  ($($key:expr => $value:expr),+ $(,)?) => {{
+    let mut table = $crate::HashTable::new();
+    table.reserve(<[&str]>::len(&[$(stringify!($key)),+]));
+    $(
+      table.insert($key, $value);
+    )+
+    table
+  }};
+}
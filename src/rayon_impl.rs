@@ -0,0 +1,52 @@
+use core::hash::BuildHasher;
+use core::hash::Hash;
+
+use rayon::iter::plumbing::UnindexedConsumer;
+use rayon::iter::IntoParallelIterator;
+use rayon::iter::ParallelIterator;
+
+use crate::hash_table::HashEntry;
+use crate::HashPolicy;
+use crate::HashTable;
+
+pub struct ParIter<'a, K, V> {
+  buckets: &'a [HashEntry<(K, V)>],
+}
+
+impl<'a, K, V> ParallelIterator for ParIter<'a, K, V>
+where
+  K: Sync,
+  V: Sync,
+{
+  type Item = (&'a K, &'a V);
+
+  fn drive_unindexed<C>(self, consumer: C) -> C::Result
+  where
+    C: UnindexedConsumer<Self::Item>,
+  {
+    // Rayon splits the flat bucket slice on its own; each piece just skips
+    // the empty slots it ends up with.
+    self
+      .buckets
+      .into_par_iter()
+      .filter_map(|entry| entry.value.as_ref().map(|(key, value)| (key, value)))
+      .drive_unindexed(consumer)
+  }
+}
+
+impl<'a, K, V, H, P> IntoParallelIterator for &'a HashTable<K, V, H, P>
+where
+  K: Hash + Eq + Sync,
+  V: Sync,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  type Iter = ParIter<'a, K, V>;
+  type Item = (&'a K, &'a V);
+
+  fn into_par_iter(self) -> Self::Iter {
+    ParIter {
+      buckets: self.buckets(),
+    }
+  }
+}
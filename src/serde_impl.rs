@@ -0,0 +1,83 @@
+use core::fmt;
+use core::hash::BuildHasher;
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+use serde::de::MapAccess;
+use serde::de::Visitor;
+use serde::ser::SerializeMap;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+use crate::HashPolicy;
+use crate::HashTable;
+
+// Upper bound on the up-front reservation taken from an untrusted
+// `size_hint`, so a malicious length prefix can't force a huge allocation.
+const MAX_PREALLOCATED_ELEMENTS: usize = 1 << 16;
+
+impl<K, V, H, P> Serialize for HashTable<K, V, H, P>
+where
+  K: Hash + Eq + Serialize,
+  V: Serialize,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(self.len()))?;
+    for (key, value) in self.iter() {
+      map.serialize_entry(key, value)?;
+    }
+    map.end()
+  }
+}
+
+struct HashTableVisitor<K, V, H, P> {
+  _marker: PhantomData<(K, V, H, P)>,
+}
+
+impl<'de, K, V, H, P> Visitor<'de> for HashTableVisitor<K, V, H, P>
+where
+  K: Hash + Eq + Deserialize<'de>,
+  V: Deserialize<'de>,
+  H: BuildHasher + Default + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  type Value = HashTable<K, V, H, P>;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("a map")
+  }
+
+  fn visit_map<A: MapAccess<'de>>(
+    self,
+    mut map: A,
+  ) -> Result<Self::Value, A::Error> {
+    let mut table = HashTable::new();
+    table.reserve(map.size_hint().unwrap_or(0).min(MAX_PREALLOCATED_ELEMENTS));
+
+    while let Some((key, value)) = map.next_entry()? {
+      table.insert(key, value);
+    }
+
+    Ok(table)
+  }
+}
+
+impl<'de, K, V, H, P> Deserialize<'de> for HashTable<K, V, H, P>
+where
+  K: Hash + Eq + Deserialize<'de>,
+  V: Deserialize<'de>,
+  H: BuildHasher + Default + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn deserialize<D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    deserializer.deserialize_map(HashTableVisitor {
+      _marker: PhantomData,
+    })
+  }
+}
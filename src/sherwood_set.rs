@@ -0,0 +1,212 @@
+use core::borrow::Borrow;
+use core::hash::BuildHasher;
+use core::hash::Hash;
+
+use crate::hash_table::HashPolicy;
+use crate::hash_table::HashTable;
+use crate::hash_table::Keys;
+use crate::hash_table::PowerOf2HashPolicy;
+
+/// A set of values stored as the keys of a `HashTable<T, ()>`.
+pub struct SherwoodSet<
+  T,
+  #[cfg(feature = "std")] H = std::collections::hash_map::RandomState,
+  #[cfg(not(feature = "std"))] H,
+  P = PowerOf2HashPolicy,
+> where
+  T: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  table: HashTable<T, (), H, P>,
+}
+
+impl<T, H, P> Default for SherwoodSet<T, H, P>
+where
+  T: Hash + Eq,
+  H: BuildHasher + Default + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T, H, P> core::fmt::Debug for SherwoodSet<T, H, P>
+where
+  T: Hash + Eq + core::fmt::Debug,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_set().entries(self.iter()).finish()
+  }
+}
+
+impl<T, H, P> Clone for SherwoodSet<T, H, P>
+where
+  T: Hash + Eq + Clone,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn clone(&self) -> Self {
+    Self {
+      table: self.table.clone(),
+    }
+  }
+}
+
+impl<T, H, P> PartialEq for SherwoodSet<T, H, P>
+where
+  T: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn eq(&self, other: &Self) -> bool {
+    self.table == other.table
+  }
+}
+
+impl<T, H, P> Eq for SherwoodSet<T, H, P>
+where
+  T: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+}
+
+impl<T, H, P> FromIterator<T> for SherwoodSet<T, H, P>
+where
+  T: Hash + Eq,
+  H: BuildHasher + Default + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    let mut set = Self::new();
+    set.extend(iter);
+    set
+  }
+}
+
+impl<T, H, P> Extend<T> for SherwoodSet<T, H, P>
+where
+  T: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+    self.table.extend(iter.into_iter().map(|value| (value, ())));
+  }
+}
+
+impl<'a, T, H, P> IntoIterator for &'a SherwoodSet<T, H, P>
+where
+  T: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  type Item = &'a T;
+  type IntoIter = Keys<'a, T, ()>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl<T, H, P> SherwoodSet<T, H, P>
+where
+  T: Hash + Eq,
+  H: BuildHasher + Default + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  pub fn new() -> Self {
+    Self::with_hasher(H::default())
+  }
+
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self {
+      table: HashTable::with_capacity(capacity),
+    }
+  }
+}
+
+impl<T, H, P> SherwoodSet<T, H, P>
+where
+  T: Hash + Eq,
+  H: BuildHasher + Clone,
+  P: HashPolicy + Default + Clone,
+{
+  pub fn with_hasher(build_hasher: H) -> Self {
+    Self {
+      table: HashTable::with_hasher(build_hasher),
+    }
+  }
+
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.table.len()
+  }
+
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.table.is_empty()
+  }
+
+  /// Adds `value`, returning whether it was new. An equal value already in
+  /// the set is kept.
+  pub fn insert(&mut self, value: T) -> bool {
+    self.table.try_insert(value, ()).is_ok()
+  }
+
+  pub fn contains<Q>(&self, value: &Q) -> bool
+  where
+    T: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self.table.contains_key(value)
+  }
+
+  /// Removes `value`, returning whether it was present.
+  pub fn remove<Q>(&mut self, value: &Q) -> bool
+  where
+    T: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self.table.remove(value).is_some()
+  }
+
+  #[inline]
+  pub fn clear(&mut self) {
+    self.table.clear();
+  }
+
+  pub fn iter(&self) -> Keys<'_, T, ()> {
+    self.table.keys()
+  }
+
+  /// Values in either set, each once.
+  pub fn union<'a>(
+    &'a self,
+    other: &'a Self,
+  ) -> impl Iterator<Item = &'a T> + 'a {
+    self
+      .iter()
+      .chain(other.iter().filter(move |value| !self.contains(*value)))
+  }
+
+  /// Values in both sets.
+  pub fn intersection<'a>(
+    &'a self,
+    other: &'a Self,
+  ) -> impl Iterator<Item = &'a T> + 'a {
+    self.iter().filter(move |value| other.contains(*value))
+  }
+
+  /// Values in `self` but not in `other`.
+  pub fn difference<'a>(
+    &'a self,
+    other: &'a Self,
+  ) -> impl Iterator<Item = &'a T> + 'a {
+    self.iter().filter(move |value| !other.contains(*value))
+  }
+}
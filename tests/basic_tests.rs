@@ -1,6 +1,25 @@
 extern crate sherwood_table;
 
+mod common;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::hash::RandomState;
+
+use sherwood_table::hashtable;
 use sherwood_table::HashTable;
+use sherwood_table::TryReserveError;
+use sherwood_table::MAX_CAPACITY;
+
+use common::allocations;
+use common::live_bytes;
+use common::CountingAllocator;
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
 
 #[test]
 fn test_empty_table() {
@@ -41,6 +60,114 @@ fn test_insert_overwrite() {
   assert_eq!(table.len(), 1);
 }
 
+#[test]
+fn test_insert_unique_unchecked() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+
+  for i in 0..10_000 {
+    let value = table.insert_unique_unchecked(i, i);
+    *value *= 2;
+  }
+
+  assert_eq!(table.len(), 10_000);
+  for i in 0..10_000 {
+    assert_eq!(table.get(&i), Some(&(i * 2)));
+  }
+}
+
+#[test]
+fn test_get_by_hash() {
+  let mut table: HashTable<String, i32> = HashTable::new();
+  for i in 0..100 {
+    table.insert(format!("key_{}", i), i);
+  }
+
+  let hash = table.hasher().hash_one("key_42");
+  assert_eq!(table.get_by_hash(hash, |k| k == "key_42"), Some(&42));
+  assert_eq!(table.get_by_hash(hash, |k| k.ends_with("42")), Some(&42));
+  assert_eq!(table.get_by_hash(hash, |_| false), None);
+
+  let missing = table.hasher().hash_one("missing");
+  assert_eq!(table.get_by_hash(missing, |k| k == "missing"), None);
+}
+
+#[derive(PartialEq, Eq)]
+struct Account {
+  id: u32,
+  owner: String,
+}
+
+// Only the id is hashed, so a lookup knowing just the id can rebuild the
+// hash.
+impl Hash for Account {
+  fn hash<S: Hasher>(&self, state: &mut S) {
+    self.id.hash(state);
+  }
+}
+
+#[test]
+fn test_get_by() {
+  let mut table: HashTable<Account, i32> = HashTable::new();
+  for i in 0..100 {
+    let account = Account {
+      id: i,
+      owner: format!("owner_{}", i),
+    };
+    table.insert(account, i as i32 * 10);
+  }
+
+  let hash = table.hasher().hash_one(42u32);
+  assert_eq!(table.get_by(hash, |account| account.id == 42), Some(&420));
+  assert_eq!(
+    table.get_by(hash, |account| account.owner == "owner_42"),
+    Some(&420)
+  );
+  assert_eq!(table.get_by(hash, |account| account.id == 43), None);
+
+  let missing = table.hasher().hash_one(1000u32);
+  assert_eq!(table.get_by(missing, |account| account.id == 1000), None);
+}
+
+#[test]
+fn test_replace() {
+  let mut table: HashTable<i32, &str> = HashTable::new();
+  assert_eq!(table.replace(1, "one"), None);
+  assert_eq!(table.replace(1, "uno"), Some("one"));
+  assert_eq!(table.get(&1), Some(&"uno"));
+  assert_eq!(table.len(), 1);
+}
+
+#[test]
+fn test_get_or_replace_if() {
+  let mut table: HashTable<&str, u32> = HashTable::new();
+  let newer = |new: u32| move |old: &u32| new > *old;
+
+  assert_eq!(table.get_or_replace_if("a", 5, newer(5)), None);
+  assert_eq!(table.get(&"a"), Some(&5));
+
+  assert_eq!(table.get_or_replace_if("a", 3, newer(3)), None);
+  assert_eq!(table.get(&"a"), Some(&5));
+
+  assert_eq!(table.get_or_replace_if("a", 8, newer(8)), Some(5));
+  assert_eq!(table.get(&"a"), Some(&8));
+  assert_eq!(table.len(), 1);
+}
+
+#[test]
+fn test_contains_value() {
+  let mut table: HashTable<i32, String> = HashTable::new();
+  assert!(!table.contains_value(&"one".to_string()));
+
+  for i in 0..100 {
+    table.insert(i, format!("value_{}", i));
+  }
+  assert!(table.contains_value(&"value_42".to_string()));
+  assert!(!table.contains_value(&"value_100".to_string()));
+
+  table.remove(&42);
+  assert!(!table.contains_value(&"value_42".to_string()));
+}
+
 #[test]
 fn test_get_mut() {
   let mut table: HashTable<i32, String> = HashTable::new();
@@ -72,6 +199,94 @@ fn test_with_capacity() {
   }
 }
 
+#[test]
+fn test_with_capacity_never_resizes() {
+  for capacity in [1, 16, 100, 1000, 4096] {
+    let mut table: HashTable<usize, usize> = HashTable::with_capacity(capacity);
+    let bucket_count = table.bucket_count();
+    assert!(table.capacity() >= capacity);
+
+    for i in 0..capacity {
+      table.insert(i, i);
+    }
+    assert_eq!(table.bucket_count(), bucket_count, "capacity {}", capacity);
+  }
+
+  let table: HashTable<usize, usize> = HashTable::with_capacity(0);
+  assert_eq!(table.bucket_count(), 0);
+}
+
+#[test]
+fn test_fill_to_capacity_does_not_resize() {
+  for factor in [0.25, 0.5, 0.9, 0.99, 0.999] {
+    for requested in [1, 200, 1000] {
+      let mut table: HashTable<usize, usize> =
+        HashTable::with_max_load_factor(requested, factor);
+      let bucket_count = table.bucket_count();
+      let capacity = table.capacity();
+      assert!(capacity >= requested);
+
+      for i in 0..capacity {
+        table.insert(i, i);
+      }
+      assert_eq!(
+        table.bucket_count(),
+        bucket_count,
+        "factor {} with {} buckets",
+        factor,
+        bucket_count
+      );
+
+      table.insert(capacity, capacity);
+      assert!(table.bucket_count() > bucket_count, "factor {}", factor);
+    }
+  }
+}
+
+#[test]
+fn test_capacity_for() {
+  assert_eq!(HashTable::<usize, usize>::capacity_for(0), 0);
+
+  for len in [1, 16, 100, 1000, 5000] {
+    let buckets = HashTable::<usize, usize>::capacity_for(len);
+    assert!(buckets >= len * 2);
+    assert_eq!(
+      HashTable::<usize, usize>::with_capacity(len).bucket_count(),
+      buckets
+    );
+
+    let mut table: HashTable<usize, usize> = HashTable::with_capacity(buckets);
+    let bucket_count = table.bucket_count();
+    for i in 0..len {
+      table.insert(i, i);
+    }
+    assert_eq!(table.bucket_count(), bucket_count, "len {}", len);
+  }
+}
+
+#[test]
+fn test_resize_to_fit_exact() {
+  let mut table: HashTable<i32, i32> = HashTable::with_capacity(10_000);
+  for i in 0..100 {
+    table.insert(i, i);
+  }
+  assert!(table.bucket_count() > HashTable::<i32, i32>::capacity_for(100));
+
+  table.resize_to_fit_exact();
+  assert_eq!(
+    table.bucket_count(),
+    HashTable::<i32, i32>::capacity_for(100)
+  );
+  for i in 0..100 {
+    assert_eq!(table.get(&i), Some(&i));
+  }
+
+  table.set_max_load_factor(0.9);
+  table.resize_to_fit_exact();
+  assert!(table.bucket_count() < HashTable::<i32, i32>::capacity_for(100));
+  assert_eq!(table.len(), 100);
+}
+
 #[test]
 fn test_clone() {
   let mut original: HashTable<i32, String> = HashTable::new();
@@ -97,6 +312,99 @@ fn test_clone() {
   assert_eq!(cloned.get(&5), None);
 }
 
+#[test]
+fn test_clone_layout() {
+  let mut original: HashTable<u64, String> = HashTable::new();
+  for i in 0..5000 {
+    original.insert(i * 7, format!("value_{}", i));
+  }
+  for i in (0..5000).step_by(3) {
+    original.remove(&(i * 7));
+  }
+  for i in (1..5000).step_by(5) {
+    original.remove_fast(&(i * 7));
+  }
+
+  let copy = original.clone_layout();
+  assert_eq!(copy.bucket_count(), original.bucket_count());
+  assert_eq!(
+    copy.probe_distance_histogram(),
+    original.probe_distance_histogram()
+  );
+  assert!(copy
+    .entries_with_distance()
+    .eq(original.entries_with_distance()));
+
+  let mut copy = copy;
+  copy.insert(1, "one".to_string());
+  assert_eq!(copy.len(), original.len() + 1);
+  assert!(!original.contains_key(&1));
+  for (key, value) in &original {
+    assert_eq!(copy.get(key), Some(value));
+  }
+}
+
+#[test]
+fn test_clone_from_reuses_allocation() {
+  let source: HashTable<u64, u64> = (0..10_000).map(|i| (i, i * 2)).collect();
+  let mut target: HashTable<u64, u64> = HashTable::new();
+  target.reserve(source.len());
+  target.insert(1, 1);
+  target.insert(20_000, 0);
+  let bucket_count = target.bucket_count();
+
+  let before = allocations();
+  target.clone_from(&source);
+  assert_eq!(allocations(), before);
+
+  assert_eq!(target.bucket_count(), bucket_count);
+  assert_eq!(target, source);
+  assert_eq!(target.get(&20_000), None);
+
+  // Too small to reuse: falls back to a fresh clone.
+  let mut small: HashTable<u64, u64> = HashTable::new();
+  small.insert(1, 1);
+  small.clone_from(&source);
+  assert_eq!(small, source);
+}
+
+#[test]
+fn test_memory_usage() {
+  let heap_before = live_bytes();
+  let mut table: HashTable<u64, u64> = HashTable::new();
+  let inline_size = std::mem::size_of::<HashTable<u64, u64>>();
+  assert_eq!(table.memory_usage(), inline_size);
+
+  table.insert(0, 0);
+  let mut usage = table.memory_usage();
+  let mut bucket_count = table.bucket_count();
+  assert!(
+    usage
+      >= inline_size + (bucket_count + 64) * std::mem::size_of::<(u64, u64)>()
+  );
+
+  let mut resizes = 0;
+  for i in 1..10_000 {
+    table.insert(i, i);
+    if table.bucket_count() != bucket_count {
+      assert!(table.memory_usage() > usage);
+      usage = table.memory_usage();
+      bucket_count = table.bucket_count();
+      resizes += 1;
+    }
+  }
+  assert!(resizes > 0);
+  // Everything the table holds on the heap, and nothing else.
+  assert_eq!(
+    (live_bytes() - heap_before) as usize,
+    table.memory_usage() - inline_size
+  );
+
+  table.clear_and_shrink();
+  assert!(table.memory_usage() < usage);
+  assert!(table.memory_usage() >= inline_size);
+}
+
 #[test]
 fn test_different_key_types() {
   let mut string_table: HashTable<String, i32> = HashTable::new();
@@ -159,3 +467,629 @@ fn test_clear_by_removing_all() {
   }
 }
 
+#[test]
+fn test_clear() {
+  let mut table: HashTable<i32, String> = HashTable::new();
+
+  for i in 0..100 {
+    table.insert(i, format!("value_{}", i));
+  }
+
+  let capacity = table.capacity();
+  table.clear();
+
+  assert_eq!(table.len(), 0);
+  assert!(table.is_empty());
+  assert_eq!(table.capacity(), capacity);
+  for i in 0..100 {
+    assert_eq!(table.get(&i), None);
+  }
+
+  for i in 0..100 {
+    table.insert(i, format!("new_value_{}", i));
+  }
+
+  assert_eq!(table.len(), 100);
+  assert_eq!(table.capacity(), capacity);
+  for i in 0..100 {
+    assert_eq!(table.get(&i), Some(&format!("new_value_{}", i)));
+  }
+}
+
+#[test]
+fn test_clear_empty_table() {
+  let mut table: HashTable<i32, String> = HashTable::new();
+  table.clear();
+
+  assert!(table.is_empty());
+  assert_eq!(table.capacity(), 0);
+}
+
+#[test]
+fn test_contains_key() {
+  let mut table: HashTable<String, i32> = HashTable::new();
+  assert!(!table.contains_key("apple"));
+
+  for i in 0..100 {
+    table.insert(format!("key_{}", i), i);
+  }
+
+  for i in 0..200 {
+    let key = format!("key_{}", i);
+    assert_eq!(table.contains_key(&key), table.get(&key).is_some());
+    assert_eq!(table.contains_key(&key), i < 100);
+  }
+
+  table.remove("key_42");
+  assert!(!table.contains_key("key_42"));
+  assert!(table.contains_key("key_43"));
+}
+
+#[test]
+fn test_from_iterator() {
+  let table: HashTable<i32, i32> = (0..100).map(|i| (i, i * 2)).collect();
+
+  assert_eq!(table.len(), 100);
+  for i in 0..100 {
+    assert_eq!(table.get(&i), Some(&(i * 2)));
+  }
+
+  let table: HashTable<&str, i32> =
+    vec![("a", 1), ("b", 2), ("a", 3)].into_iter().collect();
+  assert_eq!(table.len(), 2);
+  assert_eq!(table.get("a"), Some(&3));
+  assert_eq!(table.get("b"), Some(&2));
+}
+
+#[test]
+fn test_from_array() {
+  let table: HashTable<i32, &str> =
+    HashTable::from([(1, "a"), (2, "b"), (3, "c"), (1, "d")]);
+
+  assert_eq!(table.len(), 3);
+  assert_eq!(table.get(&1), Some(&"d"));
+  assert_eq!(table.get(&2), Some(&"b"));
+  assert_eq!(table.get(&3), Some(&"c"));
+
+  let empty: HashTable<i32, i32> = HashTable::from([]);
+  assert!(empty.is_empty());
+}
+
+#[test]
+fn test_std_hashmap_round_trip() {
+  let mut table: HashTable<i32, String> = HashTable::new();
+  for i in 0..100 {
+    table.insert(i, format!("value_{}", i));
+  }
+
+  let map: HashMap<i32, String> = table.clone().into();
+  assert_eq!(map.len(), 100);
+  for i in 0..100 {
+    assert_eq!(map.get(&i), Some(&format!("value_{}", i)));
+  }
+
+  let round_tripped: HashTable<i32, String> = HashTable::from(map);
+  assert_eq!(round_tripped, table);
+}
+
+#[test]
+fn test_hashtable_macro() {
+  let table = hashtable! { "a" => 1, "b" => 2 };
+  assert_eq!(table.get("a"), Some(&1));
+  assert_eq!(table.get("b"), Some(&2));
+  assert_eq!(table.len(), 2);
+
+  let table = hashtable! {
+    1 => "one".to_string(),
+    2 => "two".to_string(),
+    1 => "uno".to_string(),
+  };
+  assert_eq!(table.len(), 2);
+  assert_eq!(table.get(&1), Some(&"uno".to_string()));
+
+  let empty: HashTable<i32, i32> = hashtable! {};
+  assert!(empty.is_empty());
+}
+
+#[test]
+fn test_extend() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  table.insert(0, -1);
+
+  table.extend((0..50).map(|i| (i, i)));
+  table.extend(vec![(100, 100), (101, 101)]);
+
+  assert_eq!(table.len(), 52);
+  assert_eq!(table.get(&0), Some(&0));
+  assert_eq!(table.get(&49), Some(&49));
+  assert_eq!(table.get(&101), Some(&101));
+}
+
+#[test]
+fn test_append() {
+  let mut table: HashTable<i32, i32> = (0..100).map(|i| (i, i)).collect();
+  let mut other: HashTable<i32, i32> = (100..250).map(|i| (i, -i)).collect();
+  other.insert(0, 1000);
+
+  table.append(&mut other);
+
+  assert!(other.is_empty());
+  assert_eq!(other.iter().count(), 0);
+  assert_eq!(table.len(), 250);
+  for i in 1..100 {
+    assert_eq!(table.get(&i), Some(&i));
+  }
+  for i in 100..250 {
+    assert_eq!(table.get(&i), Some(&-i));
+  }
+  assert_eq!(table.get(&0), Some(&1000));
+}
+
+#[test]
+fn test_reserve() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  table.reserve(10_000);
+
+  let capacity = table.capacity();
+  assert!(capacity >= 10_000);
+
+  for i in 0..10_000 {
+    table.insert(i, i);
+    assert_eq!(table.capacity(), capacity);
+  }
+
+  assert_eq!(table.len(), 10_000);
+  table.reserve(0);
+  assert_eq!(table.capacity(), capacity);
+}
+
+#[test]
+fn test_reserve_exact() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  table.reserve_exact(1000);
+
+  let required = (1000.0 / table.max_load_factor()) as usize;
+  let bucket_count = table.bucket_count();
+  assert_eq!(bucket_count, required.next_power_of_two());
+
+  for i in 0..1000 {
+    table.insert(i, i);
+  }
+  assert_eq!(table.bucket_count(), bucket_count);
+
+  table.reserve_exact(0);
+  assert_eq!(table.bucket_count(), bucket_count);
+}
+
+#[test]
+fn test_try_reserve() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  assert_eq!(
+    table.try_reserve(usize::MAX),
+    Err(TryReserveError::Overflow)
+  );
+  assert_eq!(table.bucket_count(), 0);
+
+  assert_eq!(table.try_reserve(1000), Ok(()));
+  assert!(table.capacity() >= 1000);
+
+  table.insert(1, 1);
+  let bucket_count = table.bucket_count();
+  assert_eq!(
+    table.try_reserve(usize::MAX),
+    Err(TryReserveError::Overflow)
+  );
+  assert_eq!(
+    table.try_reserve(usize::MAX / 4),
+    Err(TryReserveError::Overflow)
+  );
+  assert_eq!(table.bucket_count(), bucket_count);
+  assert_eq!(table.get(&1), Some(&1));
+}
+
+#[test]
+#[should_panic(expected = "capacity overflow")]
+fn test_reserve_overflow_panics() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  table.insert(1, 1);
+  table.reserve(usize::MAX);
+}
+
+#[test]
+fn test_reserve_past_max_capacity() {
+  let mut table: HashTable<u8, ()> = HashTable::new();
+  assert_eq!(
+    table.try_reserve(MAX_CAPACITY + 1),
+    Err(TryReserveError::Overflow)
+  );
+  assert_eq!(table.bucket_count(), 0);
+
+  table.insert(1, ());
+  assert_eq!(
+    table.try_reserve(MAX_CAPACITY),
+    Err(TryReserveError::Overflow)
+  );
+  assert_eq!(table.len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "capacity overflow")]
+fn test_reserve_past_max_capacity_panics() {
+  let mut table: HashTable<u8, ()> = HashTable::new();
+  table.reserve(MAX_CAPACITY + 1);
+}
+
+#[test]
+fn test_invert() {
+  let table: HashTable<i32, i32> = (0..500).map(|i| (i, i * 2)).collect();
+
+  let inverted = table.invert();
+  assert_eq!(inverted.len(), 500);
+  for i in 0..500 {
+    assert_eq!(inverted.get(&(i * 2)), Some(&i));
+  }
+
+  let mut collapsing: HashTable<&str, i32> = HashTable::new();
+  collapsing.insert("a", 1);
+  collapsing.insert("b", 1);
+  collapsing.insert("c", 2);
+  let inverted = collapsing.invert();
+  assert_eq!(inverted.len(), 2);
+  assert!(matches!(inverted.get(&1), Some(&"a") | Some(&"b")));
+  assert_eq!(inverted.get(&2), Some(&"c"));
+}
+
+#[test]
+fn test_take() {
+  let mut table: HashTable<i32, String> = HashTable::new();
+  for i in 0..200 {
+    table.insert(i, i.to_string());
+  }
+  let capacity = table.capacity();
+  let bucket_count = table.bucket_count();
+
+  let taken = table.take();
+  assert_eq!(taken.len(), 200);
+  assert_eq!(taken.bucket_count(), bucket_count);
+  for i in 0..200 {
+    assert_eq!(taken.get(&i), Some(&i.to_string()));
+  }
+
+  assert!(table.is_empty());
+  assert_eq!(table.iter().count(), 0);
+  assert_eq!(table.get(&0), None);
+  assert_eq!(table.capacity(), capacity);
+  assert_eq!(table.bucket_count(), bucket_count);
+
+  table.insert(1, "one".to_string());
+  assert_eq!(table.get(&1), Some(&"one".to_string()));
+  assert_eq!(taken.get(&1), Some(&"1".to_string()));
+
+  let mut empty: HashTable<i32, i32> = HashTable::new();
+  assert!(empty.take().is_empty());
+}
+
+#[test]
+fn test_clear_and_shrink() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  for i in 0..1000 {
+    table.insert(i, i);
+  }
+  let capacity = table.capacity();
+
+  table.clear_and_shrink();
+  assert!(table.is_empty());
+  assert!(table.capacity() < capacity);
+
+  table.insert(1, 1);
+  assert_eq!(table.get(&1), Some(&1));
+}
+
+#[test]
+fn test_max_load_factor() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  assert_eq!(table.max_load_factor(), 0.5);
+
+  table.set_max_load_factor(0.9);
+  assert_eq!(table.max_load_factor(), 0.9);
+
+  table.insert(0, 0);
+  let capacity = table.capacity();
+  let limit = capacity as i32;
+  assert_eq!(capacity, (table.bucket_count() as f32 * 0.9) as usize);
+
+  for i in 1..limit {
+    table.insert(i, i);
+    assert_eq!(table.capacity(), capacity);
+  }
+  assert_eq!(table.len(), limit as usize);
+
+  table.set_max_load_factor(0.5);
+  assert!(table.capacity() > capacity);
+  for i in 0..limit {
+    assert_eq!(table.get(&i), Some(&i));
+  }
+}
+
+#[test]
+#[should_panic(expected = "max load factor")]
+fn test_max_load_factor_out_of_range() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  table.set_max_load_factor(1.0);
+}
+
+#[test]
+#[should_panic(expected = "max load factor must be more than twice")]
+fn test_max_load_factor_at_min_load_factor() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  table.set_min_load_factor(0.2);
+  table.set_max_load_factor(0.2);
+}
+
+#[test]
+fn test_with_max_load_factor() {
+  let mut table: HashTable<i32, i32> =
+    HashTable::with_max_load_factor(1000, 0.8);
+  assert_eq!(table.max_load_factor(), 0.8);
+
+  let bucket_count = table.bucket_count();
+  assert!(table.capacity() >= 1000);
+
+  for i in 0..700 {
+    table.insert(i, i);
+    assert_eq!(table.bucket_count(), bucket_count);
+  }
+  assert_eq!(table.len(), 700);
+}
+
+#[test]
+#[should_panic(expected = "max load factor")]
+fn test_with_max_load_factor_out_of_range() {
+  let _: HashTable<i32, i32> = HashTable::with_max_load_factor(16, 0.0);
+}
+
+#[test]
+fn test_equality_ignores_insertion_order() {
+  let mut forwards: HashTable<i32, String> = HashTable::new();
+  let mut backwards: HashTable<i32, String> = HashTable::new();
+
+  for i in 0..100 {
+    forwards.insert(i, format!("value_{}", i));
+  }
+  for i in (0..100).rev() {
+    backwards.insert(i, format!("value_{}", i));
+  }
+
+  assert_eq!(forwards, backwards);
+  assert_eq!(forwards, forwards.clone());
+
+  backwards.insert(42, "changed".to_string());
+  assert_ne!(forwards, backwards);
+
+  backwards.insert(42, "value_42".to_string());
+  backwards.remove(&0);
+  assert_ne!(forwards, backwards);
+}
+
+#[test]
+fn test_hash_ignores_insertion_order() {
+  let state = RandomState::new();
+  let mut forwards: HashTable<i32, String> = HashTable::new();
+  let mut backwards: HashTable<i32, String> = HashTable::new();
+
+  for i in 0..100 {
+    forwards.insert(i, format!("value_{}", i));
+  }
+  for i in (0..100).rev() {
+    backwards.insert(i, format!("value_{}", i));
+  }
+
+  assert_eq!(forwards, backwards);
+  assert_eq!(state.hash_one(&forwards), state.hash_one(&backwards));
+
+  let mut outer: HashSet<HashTable<i32, String>> = HashSet::new();
+  outer.insert(forwards.clone());
+  assert!(outer.contains(&backwards));
+}
+
+#[test]
+fn test_hash_differs_for_unequal_tables() {
+  let state = RandomState::new();
+  let base: HashTable<i32, i32> = (0..50).map(|i| (i, i)).collect();
+  let base_hash = state.hash_one(&base);
+
+  let mut collisions = 0;
+  for i in 0..50 {
+    let mut changed = base.clone();
+    changed.insert(i, i + 1);
+    if state.hash_one(&changed) == base_hash {
+      collisions += 1;
+    }
+
+    let mut removed = base.clone();
+    removed.remove(&i);
+    if state.hash_one(&removed) == base_hash {
+      collisions += 1;
+    }
+  }
+  assert_eq!(collisions, 0);
+
+  let empty: HashTable<i32, i32> = HashTable::new();
+  assert_ne!(state.hash_one(&empty), base_hash);
+}
+
+#[test]
+fn test_index() {
+  let mut table: HashTable<String, i32> = HashTable::new();
+  table.insert("one".to_string(), 1);
+  table.insert("two".to_string(), 2);
+
+  assert_eq!(table["one"], 1);
+  assert_eq!(table[&"two".to_string()], 2);
+}
+
+#[test]
+#[should_panic(expected = "no entry found for key")]
+fn test_index_missing_key() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  table.insert(1, 1);
+
+  let _ = table[&2];
+}
+
+#[test]
+fn test_get_many() {
+  let mut table: HashTable<String, i32> = HashTable::new();
+  for i in 0..100 {
+    table.insert(format!("key_{}", i), i);
+  }
+
+  let results = table.get_many(["key_3", "missing", "key_97", "key_3", "nope"]);
+  assert_eq!(results, [Some(&3), None, Some(&97), Some(&3), None]);
+  assert_eq!(table.get_many::<str, 0>([]), []);
+}
+
+#[test]
+fn test_get_many_mut() {
+  let mut table: HashTable<String, i32> = HashTable::new();
+  for i in 0..100 {
+    table.insert(format!("key_{}", i), i);
+  }
+
+  let [a, b] = table.get_many_mut(["key_3", "key_97"]).unwrap();
+  std::mem::swap(a, b);
+
+  assert_eq!(table.get("key_3"), Some(&97));
+  assert_eq!(table.get("key_97"), Some(&3));
+  assert_eq!(table.len(), 100);
+
+  assert!(table.get_many_mut(["key_1", "missing"]).is_none());
+  assert_eq!(table.get_many_mut::<str, 0>([]).map(|v| v.len()), Some(0));
+}
+
+#[test]
+fn test_get_many_mut_duplicate_key() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  table.insert(1, 10);
+  table.insert(2, 20);
+
+  assert!(table.get_many_mut([&1, &2, &1]).is_none());
+  assert_eq!(table.get(&1), Some(&10));
+}
+
+#[test]
+fn test_update_with() {
+  let mut table: HashTable<&str, Vec<i32>> = HashTable::new();
+  table.insert("a", vec![1]);
+
+  assert!(table.update_with("a", |v| v.push(2)));
+  assert_eq!(table.get("a"), Some(&vec![1, 2]));
+
+  let mut called = false;
+  assert!(!table.update_with("b", |_| called = true));
+  assert!(!called);
+  assert_eq!(table.len(), 1);
+}
+
+#[test]
+fn test_modify() {
+  let mut table: HashTable<&str, Vec<i32>> = HashTable::new();
+  table.insert("a", vec![3, 1, 2]);
+
+  let previous_max = table.modify("a", |v| {
+    let max = v.iter().copied().max();
+    v.push(10);
+    max
+  });
+  assert_eq!(previous_max, Some(Some(3)));
+  assert_eq!(table.get("a"), Some(&vec![3, 1, 2, 10]));
+
+  assert_eq!(table.modify("a", |v| v.len()), Some(4));
+  assert_eq!(table.modify("b", |v| v.len()), None);
+  assert_eq!(table.len(), 1);
+}
+
+#[test]
+fn test_get_pair_mut() {
+  let mut table: HashTable<String, i32> = HashTable::new();
+  for i in 0..100 {
+    table.insert(format!("key_{}", i), i);
+  }
+
+  let (a, b) = table.get_pair_mut("key_3", "key_97");
+  std::mem::swap(a.unwrap(), b.unwrap());
+  assert_eq!(table.get("key_3"), Some(&97));
+  assert_eq!(table.get("key_97"), Some(&3));
+
+  let (a, b) = table.get_pair_mut("key_97", "missing");
+  *a.unwrap() += 1;
+  assert!(b.is_none());
+  let (a, b) = table.get_pair_mut("missing", "key_3");
+  assert!(a.is_none());
+  assert_eq!(b, Some(&mut 97));
+  assert_eq!(table.get("key_97"), Some(&4));
+}
+
+#[test]
+fn test_get_pair_mut_same_key() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  table.insert(1, 10);
+
+  let (a, b) = table.get_pair_mut(&1, &1);
+  *a.unwrap() += 1;
+  assert!(b.is_none());
+  assert_eq!(table.get(&1), Some(&11));
+}
+
+#[test]
+fn test_capacity_and_bucket_count() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  assert_eq!(table.capacity(), 0);
+  assert_eq!(table.bucket_count(), 0);
+
+  table.insert(0, 0);
+  let capacity = table.capacity();
+  let bucket_count = table.bucket_count();
+  assert!(bucket_count.is_power_of_two());
+  assert_eq!(capacity, bucket_count / 2);
+
+  for i in 1..capacity as i32 {
+    table.insert(i, i);
+  }
+  assert_eq!(table.len(), capacity);
+  assert_eq!(table.bucket_count(), bucket_count);
+
+  table.insert(capacity as i32, 0);
+  assert!(table.capacity() > capacity);
+  assert!(table.bucket_count() > bucket_count);
+}
+
+#[test]
+fn test_debug_shows_entries_only() {
+  let mut table: HashTable<i32, &str> = HashTable::new();
+  assert_eq!(format!("{:?}", table), "{}");
+
+  table.insert(1, "one");
+  assert_eq!(format!("{:?}", table), r#"{1: "one"}"#);
+
+  table.insert(2, "two");
+  let formatted = format!("{:?}", table);
+  assert!(formatted.contains(r#"1: "one""#));
+  assert!(formatted.contains(r#"2: "two""#));
+  assert!(!formatted.contains("desired_distance"));
+}
+
+#[test]
+fn test_key_set_counts() {
+  let a: HashTable<i32, i32> = (0..100).map(|i| (i, i)).collect();
+  let b: HashTable<i32, i32> = (70..170).map(|i| (i, -i)).collect();
+
+  assert_eq!(a.key_intersection_count(&b), 30);
+  assert_eq!(b.key_intersection_count(&a), 30);
+  assert_eq!(a.key_union_count(&b), 170);
+  assert_eq!(a.key_difference_count(&b), 70);
+
+  let small: HashTable<i32, i32> = (95..105).map(|i| (i, 0)).collect();
+  assert_eq!(a.key_intersection_count(&small), 5);
+  assert_eq!(small.key_difference_count(&a), 5);
+}
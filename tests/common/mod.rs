@@ -0,0 +1,69 @@
+// Helpers shared by the integration tests. Each test binary compiles its
+// own copy and uses only some of them.
+#![allow(dead_code)]
+
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::cell::Cell;
+use std::hash::BuildHasher;
+use std::hash::Hasher;
+
+thread_local! {
+  static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+  static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+// Counts allocations and live heap bytes per thread so tests running in
+// parallel don't see each other's. A test binary opts in by registering
+// it as its `#[global_allocator]`.
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ALLOCATIONS.with(|count| count.set(count.get() + 1));
+    LIVE_BYTES.with(|bytes| bytes.set(bytes.get() + layout.size() as isize));
+    unsafe { System.alloc(layout) }
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    LIVE_BYTES.with(|bytes| bytes.set(bytes.get() - layout.size() as isize));
+    unsafe { System.dealloc(ptr, layout) }
+  }
+}
+
+pub fn allocations() -> usize {
+  ALLOCATIONS.with(Cell::get)
+}
+
+// Heap bytes allocated and not yet freed on this thread. Only differences
+// between two readings mean anything.
+pub fn live_bytes() -> isize {
+  LIVE_BYTES.with(Cell::get)
+}
+
+// Hashes integer keys to themselves, so tests can place keys in chosen
+// buckets.
+#[derive(Clone, Default)]
+pub struct IdentityHasher(u64);
+impl Hasher for IdentityHasher {
+  fn finish(&self) -> u64 {
+    self.0
+  }
+
+  fn write(&mut self, bytes: &[u8]) {
+    for &byte in bytes.iter().rev() {
+      self.0 = (self.0 << 8) | byte as u64;
+    }
+  }
+}
+
+#[derive(Clone, Default)]
+pub struct IdentityHashBuilder;
+impl BuildHasher for IdentityHashBuilder {
+  type Hasher = IdentityHasher;
+
+  fn build_hasher(&self) -> Self::Hasher {
+    IdentityHasher(0)
+  }
+}
@@ -0,0 +1,75 @@
+extern crate sherwood_table;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::BuildHasher;
+use std::hash::Hasher;
+
+use sherwood_table::HashTableEq;
+
+#[derive(Default)]
+struct CaseInsensitiveHasher(DefaultHasher);
+impl Hasher for CaseInsensitiveHasher {
+  fn finish(&self) -> u64 {
+    self.0.finish()
+  }
+
+  fn write(&mut self, bytes: &[u8]) {
+    for byte in bytes {
+      self.0.write_u8(byte.to_ascii_lowercase());
+    }
+  }
+}
+
+#[derive(Clone, Default)]
+struct CaseInsensitiveHashBuilder;
+impl BuildHasher for CaseInsensitiveHashBuilder {
+  type Hasher = CaseInsensitiveHasher;
+
+  fn build_hasher(&self) -> Self::Hasher {
+    CaseInsensitiveHasher::default()
+  }
+}
+
+fn eq_ignore_case(a: &&str, b: &&str) -> bool {
+  a.eq_ignore_ascii_case(b)
+}
+
+#[test]
+fn test_case_insensitive_keys() {
+  let mut table: HashTableEq<&str, i32, _, CaseInsensitiveHashBuilder> =
+    HashTableEq::new(eq_ignore_case);
+
+  assert_eq!(table.insert("hello", 1), None);
+  assert_eq!(table.get(&"HELLO"), Some(&1));
+  assert_eq!(table.get(&"hElLo"), Some(&1));
+  assert!(table.contains_key(&"Hello"));
+  assert_eq!(table.get(&"world"), None);
+
+  assert_eq!(table.insert("HELLO", 2), Some(1));
+  assert_eq!(table.len(), 1);
+  assert_eq!(table.iter().next(), Some((&"hello", &2)));
+
+  *table.get_mut(&"Hello").unwrap() += 1;
+  assert_eq!(table.remove(&"hello"), Some(3));
+  assert!(table.is_empty());
+}
+
+#[test]
+fn test_custom_eq_across_resize() {
+  let words: Vec<String> = (0..1000).map(|i| format!("Word{}", i)).collect();
+  let shouted: Vec<String> = words.iter().map(|w| w.to_uppercase()).collect();
+  let mut table: HashTableEq<&str, usize, _, CaseInsensitiveHashBuilder> =
+    HashTableEq::with_hasher(
+      |a: &&str, b: &&str| a.eq_ignore_ascii_case(b),
+      CaseInsensitiveHashBuilder,
+    );
+
+  for (i, word) in words.iter().enumerate() {
+    table.insert(word.as_str(), i);
+  }
+  assert_eq!(table.len(), 1000);
+
+  for (i, word) in shouted.iter().enumerate() {
+    assert_eq!(table.get(&word.as_str()), Some(&i));
+  }
+}
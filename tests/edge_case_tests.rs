@@ -1,9 +1,13 @@
 extern crate sherwood_table;
 
+use std::cell::Cell;
 use std::collections::hash_map::RandomState;
 use std::hash::BuildHasher;
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::Mutex;
 
 use sherwood_table::HashPolicy;
 use sherwood_table::HashTable;
@@ -54,8 +58,8 @@ struct LargeKey {
 impl LargeKey {
   fn new(id: usize) -> Self {
     let mut data = [0u8; 1024];
-    for i in 0..1024 {
-      data[i] = ((id + i) % 256) as u8;
+    for (i, byte) in data.iter_mut().enumerate() {
+      *byte = ((id + i) % 256) as u8;
     }
     Self { data, id }
   }
@@ -98,6 +102,34 @@ impl HashPolicy for HighLoadFactorPolicy {
   fn reset(&mut self) {}
 }
 
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct FixedSizePolicy;
+
+impl HashPolicy for FixedSizePolicy {
+  fn new_capacity(&self, _capacity: usize) -> usize {
+    64
+  }
+
+  fn hash_index(&self, hash: u64, num_slots: usize) -> usize {
+    hash as usize % (num_slots + 1)
+  }
+
+  fn commit(&mut self, _smth: u64) {}
+
+  fn reset(&mut self) {}
+}
+
+#[test]
+#[should_panic(expected = "hash policy did not grow the table past 64 buckets")]
+fn test_non_growing_policy_panics_instead_of_looping() {
+  let mut table: HashTable<i32, i32, _, FixedSizePolicy> =
+    HashTable::with_hasher_and_policy(RandomState::new(), FixedSizePolicy);
+
+  for i in 0..1000 {
+    table.insert(i, i);
+  }
+}
+
 #[test]
 fn test_high_load_factor() {
   let mut table: HashTable<i32, String, _, HighLoadFactorPolicy> =
@@ -151,7 +183,7 @@ fn test_high_load_factor() {
         i,
         table.get(&i)
       );
-    } else if i >= 20 && i < 50 && i % 2 == 0 {
+    } else if (20..50).contains(&i) && i % 2 == 0 {
       assert_eq!(
         table.get(&i),
         None,
@@ -217,6 +249,140 @@ fn test_high_collision_hasher() {
   }
 }
 
+#[test]
+fn test_from_iter_with_hasher() {
+  let table: HashTable<String, i32, HighCollisionHashBuilder> =
+    HashTable::from_iter_with_hasher(
+      (0..100).map(|i| (format!("key_{}", i), i)),
+      HighCollisionHashBuilder,
+      PowerOf2HashPolicy,
+    );
+
+  assert_eq!(table.len(), 100);
+  assert_eq!(
+    table.bucket_count(),
+    HashTable::<i32, i32>::capacity_for(100)
+  );
+  for i in 0..100 {
+    assert_eq!(table.get(&format!("key_{}", i)), Some(&i));
+  }
+}
+
+#[test]
+fn test_many_colliding_keys() {
+  let mut table: HashTable<String, i32, HighCollisionHashBuilder> =
+    HashTable::with_hasher(HighCollisionHashBuilder);
+
+  // Every key lands in the same bucket, so the probe chain grows to the
+  // full element count, well past `MIN_LOOKUPS`.
+  for i in 0..500 {
+    table.insert(format!("key_{}", i), i);
+  }
+
+  assert_eq!(table.len(), 500);
+  for i in 0..500 {
+    assert_eq!(table.get(&format!("key_{}", i)), Some(&i));
+  }
+
+  for i in (0..500).step_by(2) {
+    assert_eq!(table.remove(&format!("key_{}", i)), Some(i));
+  }
+  for i in 0..500 {
+    let expected = if i % 2 == 0 { None } else { Some(&i) };
+    assert_eq!(table.get(&format!("key_{}", i)), expected);
+  }
+}
+
+#[test]
+fn test_remove_from_dense_colliding_table() {
+  let mut table: HashTable<String, i32, HighCollisionHashBuilder> =
+    HashTable::with_hasher(HighCollisionHashBuilder);
+  table.set_max_load_factor(0.99);
+  table.insert("key_0".to_string(), 0);
+
+  let bucket_count = table.bucket_count();
+  let num_items = table.capacity() as i32;
+  for i in 1..num_items {
+    table.insert(format!("key_{}", i), i);
+  }
+  assert_eq!(table.bucket_count(), bucket_count);
+
+  let middle = num_items / 2;
+  assert_eq!(table.remove(&format!("key_{}", middle)), Some(middle));
+  assert_eq!(table.len(), num_items as usize - 1);
+  for i in 0..num_items {
+    let expected = if i == middle { None } else { Some(&i) };
+    assert_eq!(table.get(&format!("key_{}", i)), expected);
+  }
+}
+
+#[test]
+fn test_probe_lengths() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  for i in 0..1000 {
+    table.insert(i, i);
+  }
+
+  let distances: Vec<i32> = table.probe_lengths().map(|(_, d)| d).collect();
+  assert_eq!(distances.len(), 1000);
+  let at_home = distances.iter().filter(|&&d| d == 0).count();
+  assert!(at_home > 500, "only {} of 1000 keys at home", at_home);
+
+  let mut colliding: HashTable<String, i32, HighCollisionHashBuilder> =
+    HashTable::with_hasher(HighCollisionHashBuilder);
+  for i in 0..100 {
+    colliding.insert(format!("key_{}", i), i);
+  }
+
+  let mut distances: Vec<i32> =
+    colliding.probe_lengths().map(|(_, d)| d).collect();
+  distances.sort();
+  assert_eq!(distances, (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_probe_length_stats() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  assert_eq!(table.mean_probe_length(), 0.0);
+  assert_eq!(table.max_probe_length(), 0);
+
+  for i in 0..1000 {
+    table.insert(i, i);
+  }
+  assert!(table.mean_probe_length() < 2.0);
+  assert!(table.max_probe_length() < 64);
+
+  let mut colliding: HashTable<String, i32, HighCollisionHashBuilder> =
+    HashTable::with_hasher(HighCollisionHashBuilder);
+  for i in 0..100 {
+    colliding.insert(format!("key_{}", i), i);
+  }
+  assert_eq!(colliding.mean_probe_length(), 49.5);
+  assert_eq!(colliding.max_probe_length(), 99);
+}
+
+#[test]
+fn test_probe_distance_histogram() {
+  let empty: HashTable<i32, i32> = HashTable::new();
+  assert!(empty.probe_distance_histogram().is_empty());
+
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  for i in 0..1000 {
+    table.insert(i, i);
+  }
+  let histogram = table.probe_distance_histogram();
+  assert_eq!(histogram.iter().sum::<usize>(), 1000);
+  assert_eq!(histogram.len() as i32, table.max_probe_length() + 1);
+  assert!(histogram[1..].iter().all(|&count| count < histogram[0]));
+
+  let mut colliding: HashTable<String, i32, HighCollisionHashBuilder> =
+    HashTable::with_hasher(HighCollisionHashBuilder);
+  for i in 0..100 {
+    colliding.insert(format!("key_{}", i), i);
+  }
+  assert_eq!(colliding.probe_distance_histogram(), vec![1; 100]);
+}
+
 #[test]
 fn test_resize_stress() {
   let mut table: HashTable<i32, i32> = HashTable::with_capacity(16);
@@ -278,3 +444,127 @@ fn test_unusual_key_types() {
   assert_eq!(table3.get(&Some(3)), None);
 }
 
+#[test]
+fn test_shrink_to_fit() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+
+  for i in 0..5000 {
+    table.insert(i, i * 2);
+  }
+  for i in 100..5000 {
+    table.remove(&i);
+  }
+
+  let capacity = table.capacity();
+  table.shrink_to_fit();
+
+  assert_eq!(table.len(), 100);
+  assert!(table.capacity() * 16 <= capacity);
+  for i in 0..100 {
+    assert_eq!(table.get(&i), Some(&(i * 2)));
+  }
+
+  let shrunk = table.capacity();
+  table.shrink_to_fit();
+  assert_eq!(table.capacity(), shrunk);
+}
+
+#[test]
+fn test_shrink_to() {
+  let mut table: HashTable<i32, i32> = HashTable::with_capacity(16);
+
+  for i in 0..5000 {
+    table.insert(i, i);
+  }
+  for i in 10..5000 {
+    table.remove(&i);
+  }
+
+  table.shrink_to(1000);
+  assert!(table.capacity() >= 1000);
+  assert!(table.capacity() < 2048);
+
+  table.shrink_to(4096);
+  assert!(table.capacity() < 2048);
+
+  for i in 0..10 {
+    assert_eq!(table.get(&i), Some(&i));
+  }
+
+  let mut empty: HashTable<i32, i32> = HashTable::new();
+  empty.shrink_to_fit();
+  assert_eq!(empty.capacity(), 0);
+}
+
+#[derive(Clone, Debug)]
+struct CountedKey {
+  id: i32,
+  hash_calls: Rc<Cell<usize>>,
+}
+
+impl PartialEq for CountedKey {
+  fn eq(&self, other: &Self) -> bool {
+    self.id == other.id
+  }
+}
+
+impl Eq for CountedKey {}
+
+impl Hash for CountedKey {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.hash_calls.set(self.hash_calls.get() + 1);
+    self.id.hash(state);
+  }
+}
+
+#[test]
+fn test_resize_reuses_cached_hashes() {
+  let hash_calls = Rc::new(Cell::new(0));
+  let key = |id| CountedKey {
+    id,
+    hash_calls: Rc::clone(&hash_calls),
+  };
+
+  let mut table: HashTable<CountedKey, i32> = HashTable::new();
+  for i in 0..1000 {
+    table.insert(key(i), i);
+  }
+  assert_eq!(hash_calls.get(), 1000);
+
+  table.resize(table.bucket_count() * 4);
+  table.shrink_to_fit();
+  assert_eq!(hash_calls.get(), 1000);
+
+  for i in 0..1000 {
+    assert_eq!(table.get(&key(i)), Some(&i));
+  }
+}
+
+#[test]
+fn test_resize_hook() {
+  let transitions = Arc::new(Mutex::new(Vec::new()));
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  let recorded = Arc::clone(&transitions);
+  table
+    .set_resize_hook(move |old, new| recorded.lock().unwrap().push((old, new)));
+
+  for i in 0..10_000 {
+    table.insert(i, i);
+  }
+
+  let transitions = transitions.lock().unwrap();
+  assert!(transitions.len() > 1);
+  assert_eq!(transitions[0].0, 0);
+  assert_eq!(transitions.last().unwrap().1, table.bucket_count());
+  assert!(transitions.iter().all(|&(old, new)| old < new));
+  for window in transitions.windows(2) {
+    assert_eq!(window[0].1, window[1].0);
+  }
+
+  // Cloning and formatting skip the hook.
+  assert_eq!(table.clone(), table);
+  let mut small: HashTable<i32, i32> = HashTable::new();
+  small.set_resize_hook(|_, _| {});
+  small.insert(1, 2);
+  assert_eq!(format!("{:?}", small), "{1: 2}");
+}
@@ -0,0 +1,426 @@
+extern crate sherwood_table;
+
+mod common;
+
+use std::cell::Cell;
+
+use sherwood_table::Entry;
+use sherwood_table::EntryRef;
+use sherwood_table::HashTable;
+
+use common::allocations;
+use common::CountingAllocator;
+use common::IdentityHashBuilder;
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[test]
+fn test_entry_counts_across_resize() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+
+  *table.entry(0).or_insert(0) += 1;
+  let initial_capacity = table.capacity();
+
+  for i in 1..200 {
+    *table.entry(i).or_insert(0) += 1;
+  }
+  for _ in 0..2 {
+    for i in 0..200 {
+      *table.entry(i).or_insert(0) += 1;
+    }
+  }
+
+  assert_eq!(table.len(), 200);
+  assert!(table.capacity() > initial_capacity);
+  for i in 0..200 {
+    assert_eq!(table.get(&i), Some(&3));
+  }
+}
+
+#[test]
+fn test_entry_combinators() {
+  let mut table: HashTable<String, Vec<i32>> = HashTable::new();
+
+  table.entry("a".to_string()).or_default().push(1);
+  table.entry("a".to_string()).or_default().push(2);
+  table
+    .entry("b".to_string())
+    .or_insert_with(|| vec![10])
+    .push(11);
+
+  table
+    .entry("a".to_string())
+    .and_modify(|v| v.push(3))
+    .or_default();
+  table
+    .entry("c".to_string())
+    .and_modify(|v| v.push(100))
+    .or_insert_with(|| vec![0]);
+
+  assert_eq!(table.len(), 3);
+  assert_eq!(table.get("a"), Some(&vec![1, 2, 3]));
+  assert_eq!(table.get("b"), Some(&vec![10, 11]));
+  assert_eq!(table.get("c"), Some(&vec![0]));
+}
+
+#[test]
+fn test_or_insert_with_key() {
+  let mut table: HashTable<String, usize> = HashTable::new();
+
+  assert_eq!(
+    *table.entry("hello".into()).or_insert_with_key(|k| k.len()),
+    5
+  );
+
+  *table.entry("hello".into()).or_insert(0) += 1;
+  let value = table
+    .entry("hello".into())
+    .or_insert_with_key(|_| panic!("key is already present"));
+  assert_eq!(*value, 6);
+  assert_eq!(table.len(), 1);
+}
+
+#[test]
+fn test_get_or_insert_with() {
+  let mut table: HashTable<i32, String> = HashTable::new();
+
+  let called = Cell::new(false);
+  let value = table.get_or_insert_with(1, || {
+    called.set(true);
+    "one".to_string()
+  });
+  value.push('!');
+  assert!(called.get());
+
+  called.set(false);
+  let value = table.get_or_insert_with(1, || {
+    called.set(true);
+    "uno".to_string()
+  });
+  assert_eq!(value, "one!");
+  assert!(!called.get());
+  assert_eq!(table.len(), 1);
+}
+
+#[test]
+fn test_get_or_insert() {
+  let mut table: HashTable<i32, Vec<i32>> = HashTable::new();
+
+  for x in 0..100 {
+    table.get_or_insert(x % 7, Vec::new()).push(x);
+  }
+
+  assert_eq!(table.len(), 7);
+  assert_eq!(
+    table.get(&3),
+    Some(&(3..100).step_by(7).collect::<Vec<_>>())
+  );
+  for k in 0..7 {
+    assert_eq!(table.get(&k).unwrap().len(), if k < 2 { 15 } else { 14 });
+  }
+}
+
+#[test]
+fn test_entry_or_default() {
+  let mut by_parity: HashTable<bool, Vec<i32>> = HashTable::new();
+
+  for i in 0..10 {
+    by_parity.entry_or_default(i % 2 == 0).push(i);
+  }
+
+  assert_eq!(by_parity.len(), 2);
+  assert_eq!(by_parity.get(&true), Some(&vec![0, 2, 4, 6, 8]));
+  assert_eq!(by_parity.get(&false), Some(&vec![1, 3, 5, 7, 9]));
+}
+
+#[test]
+fn test_modify_or_insert() {
+  let mut counts: HashTable<&str, usize> = HashTable::new();
+  let text = "the cat and the dog and the bird";
+
+  for word in text.split_whitespace() {
+    counts.modify_or_insert(word, |count| *count += 1, || 1);
+  }
+
+  assert_eq!(counts.len(), 5);
+  assert_eq!(counts.get("the"), Some(&3));
+  assert_eq!(counts.get("and"), Some(&2));
+  assert_eq!(counts.get("cat"), Some(&1));
+  assert_eq!(counts.get("bird"), Some(&1));
+
+  let value = counts.modify_or_insert("cat", |count| *count *= 10, || 0);
+  assert_eq!(*value, 10);
+  *counts.modify_or_insert("fish", |_| panic!("fish is absent"), || 7) += 1;
+  assert_eq!(counts.get("fish"), Some(&8));
+}
+
+#[test]
+fn test_insert_or_update() {
+  let mut totals: HashTable<&str, u32> = HashTable::new();
+  let sales = [("apples", 3), ("pears", 2), ("apples", 4), ("plums", 1)];
+
+  let inserted: Vec<bool> = sales
+    .iter()
+    .map(|&(fruit, amount)| {
+      totals.insert_or_update(fruit, amount, |total| *total += amount)
+    })
+    .collect();
+
+  assert_eq!(inserted, vec![true, true, false, true]);
+  assert_eq!(totals.len(), 3);
+  assert_eq!(totals.get("apples"), Some(&7));
+  assert_eq!(totals.get("pears"), Some(&2));
+  assert_eq!(totals.get("plums"), Some(&1));
+}
+
+#[test]
+fn test_entry_variants() {
+  let mut table: HashTable<i32, &str> = HashTable::new();
+  table.insert(1, "one");
+
+  assert!(matches!(table.entry(1), Entry::Occupied(_)));
+  assert!(matches!(table.entry(2), Entry::Vacant(_)));
+  assert_eq!(table.len(), 1);
+
+  match table.entry(2) {
+    Entry::Occupied(_) => panic!("key 2 should be vacant"),
+    Entry::Vacant(entry) => {
+      *entry.insert("two") = "TWO";
+    }
+  }
+
+  assert_eq!(table.get(&2), Some(&"TWO"));
+  assert_eq!(table.len(), 2);
+}
+
+#[test]
+fn test_vacant_entry_into_key() {
+  let mut table: HashTable<Vec<String>, i32> = HashTable::new();
+  table.insert(vec!["present".to_string()], 1);
+
+  let key: Vec<String> = (0..100).map(|i| format!("segment_{}", i)).collect();
+  let recovered = match table.entry(key.clone()) {
+    Entry::Occupied(_) => panic!("key should be vacant"),
+    Entry::Vacant(entry) => entry.into_key(),
+  };
+
+  assert_eq!(recovered, key);
+  assert_eq!(table.len(), 1);
+  assert!(!table.contains_key(&key));
+}
+
+#[test]
+fn test_entry_key() {
+  let mut table: HashTable<String, usize> = HashTable::new();
+  table.insert("short".into(), 1);
+
+  for word in ["short", "tiny", "much_too_long"] {
+    let entry = table.entry(word.to_string());
+    assert_eq!(entry.key(), word);
+
+    match entry {
+      Entry::Occupied(mut entry) => {
+        assert_eq!(entry.key(), "short");
+        *entry.get_mut() += 1;
+      }
+      Entry::Vacant(entry) if entry.key().len() <= 5 => {
+        let len = entry.key().len();
+        entry.insert(len);
+      }
+      Entry::Vacant(entry) => {
+        assert_eq!(entry.into_key(), "much_too_long");
+      }
+    }
+  }
+
+  assert_eq!(table.len(), 2);
+  assert_eq!(table.get("short"), Some(&2));
+  assert_eq!(table.get("tiny"), Some(&4));
+  assert!(!table.contains_key("much_too_long"));
+}
+
+#[test]
+fn test_occupied_entry() {
+  let mut table: HashTable<String, i32> = HashTable::new();
+  table.insert("a".into(), 1);
+  table.insert("b".into(), 2);
+
+  let Entry::Occupied(mut entry) = table.entry("a".into()) else {
+    panic!("key a should be occupied");
+  };
+  assert_eq!(*entry.get(), 1);
+  *entry.get_mut() += 10;
+  assert_eq!(entry.insert(20), 11);
+  *entry.into_mut() += 1;
+  assert_eq!(table.get("a"), Some(&21));
+
+  let Entry::Occupied(entry) = table.entry("a".into()) else {
+    panic!("key a should be occupied");
+  };
+  assert_eq!(entry.remove_entry(), ("a".to_string(), 21));
+  assert_eq!(table.get("a"), None);
+  assert_eq!(table.len(), 1);
+
+  table.entry("c".into()).and_modify(|v| *v += 1).or_insert(3);
+  table.entry("c".into()).and_modify(|v| *v += 1).or_insert(3);
+  match table.entry("c".into()) {
+    Entry::Occupied(entry) => assert_eq!(entry.remove(), 4),
+    Entry::Vacant(_) => panic!("key c should be occupied"),
+  }
+  assert_eq!(table.len(), 1);
+  assert_eq!(table.get("b"), Some(&2));
+}
+
+#[test]
+fn test_occupied_entry_remove_with_collisions() {
+  let mut table: HashTable<u64, u64, IdentityHashBuilder> =
+    HashTable::with_hasher(IdentityHashBuilder);
+  table.insert(0, 0);
+  let bucket_count = table.bucket_count() as u64;
+  for i in 1..5 {
+    table.insert(i * bucket_count, i);
+  }
+
+  // Removing from the front of the cluster shifts the rest back.
+  match table.entry(0) {
+    Entry::Occupied(entry) => assert_eq!(entry.remove(), 0),
+    Entry::Vacant(_) => panic!("key 0 should be occupied"),
+  }
+  for i in 1..5 {
+    assert_eq!(table.get(&(i * bucket_count)), Some(&i));
+  }
+  assert_eq!(table.max_probe_length(), 3);
+}
+
+#[test]
+fn test_try_insert() {
+  let mut table: HashTable<i32, &str> = HashTable::new();
+
+  assert_eq!(table.try_insert(1, "one").ok().copied(), Some("one"));
+
+  let err = table.try_insert(1, "uno").unwrap_err();
+  assert_eq!(err.key, 1);
+  assert_eq!(err.value, "uno");
+  assert_eq!(*err.entry_ref, "one");
+
+  assert_eq!(table.len(), 1);
+  assert_eq!(table.get(&1), Some(&"one"));
+}
+
+#[test]
+fn test_try_insert_after_displacement() {
+  let mut table: HashTable<u64, u64, IdentityHashBuilder> =
+    HashTable::with_hasher(IdentityHashBuilder);
+
+  table.insert(1, 1);
+  table.insert(0, 0);
+  let capacity = table.bucket_count() as u64;
+
+  // Collides with key 0 and steals key 1's slot, pushing it further along.
+  let value = table.try_insert(capacity, 10).unwrap();
+  *value += 5;
+
+  assert_eq!(table.bucket_count() as u64, capacity);
+  assert_eq!(table.get(&capacity), Some(&15));
+  assert_eq!(table.get(&0), Some(&0));
+  assert_eq!(table.get(&1), Some(&1));
+}
+
+#[test]
+fn test_entry_ref() {
+  let mut table: HashTable<String, i32> = HashTable::new();
+
+  *table.entry_ref("hit").or_insert(0) += 1;
+  assert_eq!(table.get("hit"), Some(&1));
+
+  let before = allocations();
+  for _ in 0..100 {
+    *table.entry_ref("hit").or_insert(0) += 1;
+  }
+  assert_eq!(allocations(), before);
+  assert_eq!(table.get("hit"), Some(&101));
+
+  assert!(matches!(table.entry_ref("hit"), EntryRef::Occupied(_)));
+  assert!(matches!(table.entry_ref("miss"), EntryRef::Vacant(_)));
+  assert_eq!(table.len(), 1);
+
+  table.entry_ref("miss").and_modify(|v| *v += 1).or_default();
+  table.entry_ref("miss").and_modify(|v| *v += 1).or_default();
+  assert_eq!(table.get("miss"), Some(&1));
+  assert_eq!(table.len(), 2);
+}
+
+#[test]
+fn test_entry_ref_hit_at_capacity() {
+  let mut table: HashTable<String, i32> = HashTable::with_capacity(100);
+  let keys: Vec<String> =
+    (0..table.capacity()).map(|i| i.to_string()).collect();
+  for key in &keys {
+    table.insert(key.clone(), 0);
+  }
+  assert_eq!(table.len(), table.capacity());
+  let bucket_count = table.bucket_count();
+
+  let before = allocations();
+  for key in &keys {
+    *table.entry_ref(key.as_str()).or_insert(0) += 1;
+  }
+  assert_eq!(allocations(), before);
+  assert_eq!(table.bucket_count(), bucket_count);
+
+  *table.entry_ref("miss").or_insert(0) += 1;
+  assert!(table.bucket_count() > bucket_count);
+  for key in &keys {
+    assert_eq!(table.get(key.as_str()), Some(&1));
+  }
+}
+
+#[test]
+fn test_get_or_insert_ref() {
+  let mut interned: HashTable<String, u32> = HashTable::new();
+  let words = ["red", "green", "blue"];
+
+  for word in words {
+    let next_id = interned.len() as u32;
+    interned.get_or_insert_ref(word, || next_id);
+  }
+  assert_eq!(interned.len(), 3);
+
+  let before = allocations();
+  for _ in 0..100 {
+    for (id, word) in words.iter().enumerate() {
+      let value = interned
+        .get_or_insert_ref(*word, || panic!("{} is already interned", word));
+      assert_eq!(*value, id as u32);
+    }
+  }
+  assert_eq!(allocations(), before);
+  assert_eq!(interned.len(), 3);
+
+  // Hits must not allocate at the resize boundary either.
+  let mut next_id = interned.len() as u32;
+  while interned.len() < interned.capacity() {
+    interned.insert(format!("word{}", next_id), next_id);
+    next_id += 1;
+  }
+  let bucket_count = interned.bucket_count();
+  let owned: Vec<String> = words.iter().map(|word| word.to_string()).collect();
+  let replacement = owned.clone();
+
+  let before = allocations();
+  for (id, word) in words.iter().enumerate() {
+    let value = interned
+      .get_or_insert_ref(*word, || panic!("{} is already interned", word));
+    assert_eq!(*value, id as u32);
+  }
+  for (id, word) in owned.into_iter().enumerate() {
+    assert!(matches!(interned.entry(word), Entry::Occupied(_)));
+    assert_eq!(interned.get(words[id]), Some(&(id as u32)));
+  }
+  for (id, word) in replacement.into_iter().enumerate() {
+    assert_eq!(interned.insert(word, id as u32), Some(id as u32));
+  }
+  assert_eq!(allocations(), before);
+  assert_eq!(interned.bucket_count(), bucket_count);
+}
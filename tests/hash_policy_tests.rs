@@ -1,12 +1,22 @@
 extern crate sherwood_table;
 
+mod common;
+
+use std::cell::RefCell;
 use std::collections::hash_map::RandomState;
 use std::hash::BuildHasher;
+use std::rc::Rc;
 
 use sherwood_table::BuildHasherWrapper;
+use sherwood_table::FibonacciHashPolicy;
+use sherwood_table::FxLikeBuildHasher;
+use sherwood_table::GeometricHashPolicy;
 use sherwood_table::HashPolicy;
 use sherwood_table::HashTable;
 use sherwood_table::PowerOf2HashPolicy;
+use sherwood_table::PrimeModulusHashPolicy;
+
+use common::IdentityHashBuilder;
 
 #[test]
 fn test_power_of_2_policy() {
@@ -19,6 +29,13 @@ fn test_power_of_2_policy() {
   assert_eq!(policy.hash_index(31, 15), 31 & 15);
 }
 
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "power-of-two bucket count")]
+fn test_power_of_2_policy_rejects_non_power_of_two() {
+  PowerOf2HashPolicy.hash_index(5, 10);
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 struct ModuloHashPolicy;
 
@@ -133,6 +150,47 @@ fn test_policy_hash_distribution() {
   }
 }
 
+#[test]
+fn test_fast_hasher_distribution() {
+  let mut table: HashTable<u64, u64, FxLikeBuildHasher> = HashTable::fast();
+  for i in 0..10_000 {
+    table.insert(i, i);
+  }
+  assert!(table.mean_probe_length() < 2.0);
+  assert!(table.max_probe_length() < 32);
+
+  // Without the finalizer these would all share their low bits and pile
+  // into a single bucket.
+  let mut table: HashTable<u64, u64, FxLikeBuildHasher> = HashTable::fast();
+  for i in 0..10_000 {
+    table.insert(i << 32, i);
+  }
+  assert!(table.mean_probe_length() < 2.0);
+  assert!(table.max_probe_length() < 32);
+  for i in 0..10_000 {
+    assert_eq!(table.get(&(i << 32)), Some(&i));
+  }
+}
+
+#[test]
+fn test_fast_hasher_seed() {
+  let a = FxLikeBuildHasher::default();
+  let b = FxLikeBuildHasher::with_seed(42);
+
+  assert_eq!(a.hash_one("key"), a.hash_one("key"));
+  assert_ne!(a.hash_one("key"), b.hash_one("key"));
+  assert_ne!(a.hash_one(1u64), a.hash_one(2u64));
+}
+
+#[test]
+fn test_with_selected_policy() {
+  let mut table = HashTable::with_selected_policy(RandomState::new());
+  table.insert(1, "one");
+
+  assert_eq!(*table.policy(), PowerOf2HashPolicy);
+  assert_eq!(table.get(&1), Some(&"one"));
+}
+
 #[test]
 fn test_build_hasher_wrapper() {
   let build_hasher = RandomState::new();
@@ -148,3 +206,242 @@ fn test_build_hasher_wrapper() {
   table.insert("test".to_string(), 42);
   assert_eq!(table.get("test"), Some(&42));
 }
+
+#[test]
+fn test_fibonacci_policy() {
+  let policy = FibonacciHashPolicy;
+
+  assert_eq!(policy.new_capacity(1), 64);
+  assert_eq!(policy.new_capacity(100), 128);
+  assert_eq!(policy.new_capacity(128), 128);
+
+  assert_eq!(policy.hash_index(12345, 0), 0);
+  for hash in 0..1000u64 {
+    assert!(policy.hash_index(hash, 63) < 64);
+    assert!(policy.hash_index(hash << 32, 1023) < 1024);
+  }
+  assert_ne!(
+    policy.hash_index(1 << 20, 63),
+    policy.hash_index(2 << 20, 63)
+  );
+}
+
+#[test]
+fn test_fibonacci_policy_distribution() {
+  let mut table: HashTable<u64, u64, _, FibonacciHashPolicy> =
+    HashTable::with_hasher_and_policy(IdentityHashBuilder, FibonacciHashPolicy);
+
+  // Keys sharing all of their low bits: plain masking would put every one
+  // of them in the same bucket.
+  for i in 0..1000u64 {
+    table.insert(i << 20, i);
+  }
+
+  assert_eq!(table.len(), 1000);
+  assert_eq!(table.bucket_count(), 2048);
+  for i in 0..1000u64 {
+    assert_eq!(table.get(&(i << 20)), Some(&i));
+  }
+}
+
+fn is_prime(n: usize) -> bool {
+  n >= 2
+    && (2..)
+      .take_while(|d| d * d <= n)
+      .all(|d| !n.is_multiple_of(d))
+}
+
+#[test]
+fn test_prime_modulus_policy() {
+  let mut policy = PrimeModulusHashPolicy::default();
+
+  assert_eq!(policy.new_capacity(0), 5);
+  assert_eq!(policy.new_capacity(64), 67);
+  assert_eq!(policy.new_capacity(67), 67);
+  assert_eq!(policy.new_capacity(68), 131);
+  assert_eq!(policy.new_capacity(1 << 40), 1_099_511_627_791);
+
+  let hashes = [0, 1, 66, 67, 68, 12_345_678, u64::MAX - 1, u64::MAX];
+  for modulus in [5u64, 67, 131, 1_048_583, 4_294_967_311] {
+    policy.commit(modulus);
+    for hash in hashes {
+      let index = policy.hash_index(hash, modulus as usize - 1);
+      assert_eq!(index as u64, hash % modulus);
+    }
+  }
+
+  policy.reset();
+  assert_eq!(policy.hash_index(200, 66), 200 % 67);
+}
+
+#[test]
+fn test_prime_modulus_policy_growth() {
+  let mut table: HashTable<u32, u32, _, PrimeModulusHashPolicy> =
+    HashTable::with_hasher_and_policy(
+      RandomState::new(),
+      PrimeModulusHashPolicy::default(),
+    );
+
+  let mut capacities = Vec::new();
+  for i in 0..200_000 {
+    table.insert(i, i);
+    if capacities.last() != Some(&table.bucket_count()) {
+      capacities.push(table.bucket_count());
+    }
+  }
+
+  assert_eq!(table.len(), 200_000);
+  for pair in capacities.windows(2) {
+    assert!(pair[1] > pair[0]);
+  }
+  for &capacity in &capacities {
+    assert!(is_prime(capacity), "{} is not prime", capacity);
+    assert_eq!(table.policy().new_capacity(capacity), capacity);
+  }
+  for i in (0..200_000).step_by(997) {
+    assert_eq!(table.get(&i), Some(&i));
+  }
+}
+
+#[test]
+fn test_geometric_policy_growth() {
+  let mut table: HashTable<u32, u32, _, GeometricHashPolicy> =
+    HashTable::with_hasher_and_policy(
+      RandomState::new(),
+      GeometricHashPolicy::new(1.5),
+    );
+
+  let mut bucket_counts = Vec::new();
+  let mut memory = Vec::new();
+  for i in 0..100_000 {
+    table.insert(i, i);
+    if bucket_counts.last() != Some(&table.bucket_count()) {
+      bucket_counts.push(table.bucket_count());
+      memory.push(table.memory_usage());
+    }
+  }
+
+  assert!(bucket_counts.len() > 10);
+  for pair in bucket_counts.windows(2) {
+    let ratio = pair[1] as f64 / pair[0] as f64;
+    assert!((1.45..1.55).contains(&ratio), "grew by {}", ratio);
+  }
+  // The 64 overflow slots after the last bucket weigh less as tables grow.
+  for pair in memory[memory.len() - 5..].windows(2) {
+    let ratio = pair[1] as f64 / pair[0] as f64;
+    assert!((1.4..1.6).contains(&ratio), "memory grew by {}", ratio);
+  }
+  for i in (0..100_000).step_by(101) {
+    assert_eq!(table.get(&i), Some(&i));
+  }
+
+  table.retain(|k, _| *k < 1000);
+  table.shrink_to_fit();
+  assert!(table.bucket_count() < *bucket_counts.last().unwrap() / 10);
+  assert_eq!(table.get(&999), Some(&999));
+}
+
+#[test]
+#[should_panic(expected = "growth factor must be greater than 1")]
+fn test_geometric_policy_rejects_shrinking_factor() {
+  GeometricHashPolicy::new(0.9);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PolicyEvent {
+  Reset,
+  Commit(u64),
+}
+
+#[derive(Clone, Default)]
+struct RecordingPolicy<P = PowerOf2HashPolicy> {
+  inner: P,
+  events: Rc<RefCell<Vec<PolicyEvent>>>,
+}
+
+impl<P: HashPolicy> HashPolicy for RecordingPolicy<P> {
+  fn new_capacity(&self, capacity: usize) -> usize {
+    self.inner.new_capacity(capacity)
+  }
+
+  fn hash_index(&self, hash: u64, num_slots: usize) -> usize {
+    self.inner.hash_index(hash, num_slots)
+  }
+
+  fn commit(&mut self, smth: u64) {
+    self.events.borrow_mut().push(PolicyEvent::Commit(smth));
+  }
+
+  fn reset(&mut self) {
+    self.events.borrow_mut().push(PolicyEvent::Reset);
+  }
+}
+
+#[test]
+fn test_policy_commit_and_reset_on_resize() {
+  let policy = RecordingPolicy::default();
+  let events = Rc::clone(&policy.events);
+
+  let mut table: HashTable<i32, i32, _, RecordingPolicy> =
+    HashTable::with_hasher_and_policy(RandomState::new(), policy);
+  assert!(events.borrow().is_empty());
+
+  let mut expected = Vec::new();
+  let mut capacity = table.bucket_count();
+  for i in 0..5000 {
+    table.insert(i, i);
+    if table.bucket_count() != capacity {
+      capacity = table.bucket_count();
+      expected.push(PolicyEvent::Reset);
+      expected.push(PolicyEvent::Commit(capacity as u64));
+    }
+  }
+
+  assert!(expected.len() > 2);
+  assert_eq!(*events.borrow(), expected);
+}
+
+#[test]
+fn test_extend_resizes_once() {
+  let policy = RecordingPolicy::default();
+  let events = Rc::clone(&policy.events);
+
+  let mut table: HashTable<i32, i32, _, RecordingPolicy> =
+    HashTable::with_hasher_and_policy(RandomState::new(), policy);
+  table.extend((0..10_000).map(|i| (i, i)));
+
+  let resizes = events
+    .borrow()
+    .iter()
+    .filter(|event| matches!(event, PolicyEvent::Commit(_)))
+    .count();
+  assert_eq!(table.len(), 10_000);
+  assert!(resizes <= 2, "extend resized {} times", resizes);
+}
+
+#[test]
+fn test_repeated_resize_is_a_no_op() {
+  let policy = RecordingPolicy::<ModuloHashPolicy>::default();
+  let events = Rc::clone(&policy.events);
+
+  let mut table: HashTable<i32, i32, _, RecordingPolicy<ModuloHashPolicy>> =
+    HashTable::with_hasher_and_policy(RandomState::new(), policy);
+  for i in 0..20 {
+    table.insert(i, i);
+  }
+
+  let rehashes = || events.borrow().len();
+  let before = rehashes();
+
+  assert_eq!(table.resize(100), 107);
+  assert_eq!(table.bucket_count(), 107);
+  assert_eq!(rehashes(), before + 2);
+
+  assert_eq!(table.resize(100), 107);
+  assert_eq!(table.resize(90), 107);
+  assert_eq!(rehashes(), before + 2);
+
+  for i in 0..20 {
+    assert_eq!(table.get(&i), Some(&i));
+  }
+}
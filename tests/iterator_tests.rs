@@ -1,9 +1,14 @@
 extern crate sherwood_table;
 
+mod common;
+
 use std::collections::HashSet;
+use std::rc::Rc;
 
 use sherwood_table::HashTable;
 
+use common::IdentityHashBuilder;
+
 #[test]
 fn test_empty_iterator() {
   let table: HashTable<i32, String> = HashTable::new();
@@ -157,9 +162,9 @@ fn test_iterator_with_modify_after_clone() {
     assert_eq!(original_items[i - 5].1, format!("value_{}", i));
   }
 
-  for i in 0..10 {
-    assert_eq!(cloned_items[i].0, i as i32);
-    assert_eq!(cloned_items[i].1, format!("value_{}", i));
+  for (i, (key, value)) in cloned_items.iter().enumerate() {
+    assert_eq!(*key, i as i32);
+    assert_eq!(*value, format!("value_{}", i));
   }
 }
 
@@ -186,3 +191,278 @@ fn test_large_iterator() {
   assert_eq!(sum, 1498500);
 }
 
+#[test]
+fn test_into_iter_owned() {
+  let mut table: HashTable<i32, String> = HashTable::new();
+
+  for i in 0..100 {
+    table.insert(i, format!("value_{}", i));
+  }
+
+  let iter = table.into_iter();
+  assert_eq!(iter.size_hint(), (100, Some(100)));
+
+  let mut collected: Vec<(i32, String)> = iter.collect();
+  assert_eq!(collected.len(), 100);
+
+  collected.sort_by_key(|(k, _)| *k);
+  for (i, (key, value)) in collected.into_iter().enumerate() {
+    assert_eq!(key, i as i32);
+    assert_eq!(value, format!("value_{}", i));
+  }
+}
+
+#[test]
+fn test_into_iter_drops_unconsumed() {
+  let marker = Rc::new(());
+  let mut table: HashTable<i32, Rc<()>> = HashTable::new();
+
+  for i in 0..50 {
+    table.insert(i, Rc::clone(&marker));
+  }
+  assert_eq!(Rc::strong_count(&marker), 51);
+
+  let mut iter = table.into_iter();
+  let taken: Vec<_> = iter.by_ref().take(10).collect();
+  assert_eq!(iter.size_hint(), (40, Some(40)));
+
+  drop(iter);
+  assert_eq!(Rc::strong_count(&marker), 11);
+
+  drop(taken);
+  assert_eq!(Rc::strong_count(&marker), 1);
+}
+
+#[test]
+fn test_iter_mut() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+
+  for i in 0..100 {
+    table.insert(i, i);
+  }
+
+  assert_eq!(table.iter_mut().size_hint(), (100, Some(100)));
+
+  for (_, v) in table.iter_mut() {
+    *v *= 2;
+  }
+
+  assert_eq!(table.len(), 100);
+  for i in 0..100 {
+    assert_eq!(table.get(&i), Some(&(i * 2)));
+  }
+}
+
+#[test]
+fn test_iter_mut_by_for_loop() {
+  let mut table: HashTable<String, i32> = HashTable::new();
+  for i in 0..100 {
+    table.insert(format!("key_{}", i), i);
+  }
+
+  for (key, value) in &mut table {
+    assert_eq!(*key, format!("key_{}", value));
+    *value += 1000;
+  }
+
+  assert_eq!(table.len(), 100);
+  for i in 0..100 {
+    assert_eq!(table.get(&format!("key_{}", i)), Some(&(i + 1000)));
+  }
+}
+
+#[test]
+fn test_keys_and_values() {
+  let mut table: HashTable<i32, String> = HashTable::new();
+
+  for i in 0..50 {
+    table.insert(i, format!("value_{}", i));
+  }
+
+  let keys: HashSet<i32> = table.keys().copied().collect();
+  assert_eq!(keys.len(), table.len());
+  assert_eq!(table.keys().size_hint(), (50, Some(50)));
+  for i in 0..50 {
+    assert!(keys.contains(&i));
+  }
+
+  let values: HashSet<&String> = table.values().collect();
+  assert_eq!(values.len(), table.len());
+  assert!(values.contains(&"value_7".to_string()));
+}
+
+#[test]
+fn test_values_mut() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+
+  for i in 0..50 {
+    table.insert(i, i);
+  }
+
+  assert_eq!(table.values_mut().size_hint(), (50, Some(50)));
+  for value in table.values_mut() {
+    *value += 1;
+  }
+
+  for i in 0..50 {
+    assert_eq!(table.get(&i), Some(&(i + 1)));
+  }
+}
+
+#[test]
+fn test_drain() {
+  let mut table: HashTable<i32, String> = HashTable::new();
+
+  for i in 0..100 {
+    table.insert(i, format!("value_{}", i));
+  }
+
+  let len = table.len();
+  let capacity = table.capacity();
+
+  let mut drained: Vec<(i32, String)> = table.drain().collect();
+  assert_eq!(drained.len(), len);
+  assert!(table.is_empty());
+  assert_eq!(table.capacity(), capacity);
+
+  drained.sort_by_key(|(k, _)| *k);
+  for (i, (key, value)) in drained.into_iter().enumerate() {
+    assert_eq!(key, i as i32);
+    assert_eq!(value, format!("value_{}", i));
+  }
+
+  table.insert(7, "seven".to_string());
+  assert_eq!(table.len(), 1);
+  assert_eq!(table.get(&7), Some(&"seven".to_string()));
+  assert_eq!(table.get(&8), None);
+}
+
+#[test]
+fn test_drain_dropped_midway() {
+  let marker = Rc::new(());
+  let mut table: HashTable<i32, Rc<()>> = HashTable::new();
+
+  for i in 0..50 {
+    table.insert(i, Rc::clone(&marker));
+  }
+
+  let taken: Vec<_> = table.drain().take(5).collect();
+  assert_eq!(taken.len(), 5);
+  assert!(table.is_empty());
+  assert_eq!(table.iter().count(), 0);
+  assert_eq!(Rc::strong_count(&marker), 6);
+}
+
+#[test]
+fn test_into_keys_and_values() {
+  let table: HashTable<i32, i32> = (0..100).map(|i| (i, i)).collect();
+  let values = table.clone().into_values();
+  assert_eq!(values.size_hint(), (100, Some(100)));
+  assert_eq!(values.sum::<i32>(), 4950);
+
+  let mut keys: Vec<i32> = table.into_keys().collect();
+  keys.sort();
+  assert_eq!(keys, (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_exact_size_and_fused() {
+  let mut table: HashTable<i32, i32> = (0..100).map(|i| (i, i)).collect();
+
+  let mut iter = table.iter();
+  assert_eq!(iter.len(), table.len());
+  iter.next();
+  assert_eq!(iter.len(), 99);
+  for _ in iter.by_ref() {}
+  assert_eq!(iter.len(), 0);
+  assert_eq!(iter.next(), None);
+  assert_eq!(iter.next(), None);
+
+  assert_eq!(table.keys().len(), 100);
+  assert_eq!(table.values().len(), 100);
+  assert_eq!(table.values_mut().len(), 100);
+  assert_eq!(table.iter_mut().len(), 100);
+  assert_eq!(table.clone().into_iter().len(), 100);
+  assert_eq!(table.clone().into_keys().len(), 100);
+  assert_eq!(table.clone().into_values().len(), 100);
+
+  let mut drain = table.drain();
+  assert_eq!(drain.len(), 100);
+  for _ in drain.by_ref() {}
+  assert!(drain.next().is_none());
+  assert!(drain.next().is_none());
+}
+
+#[test]
+fn test_entries_with_distance() {
+  let mut table: HashTable<u64, u64, IdentityHashBuilder> =
+    HashTable::with_hasher(IdentityHashBuilder);
+  table.reserve(100);
+  let bucket_count = table.bucket_count() as u64;
+
+  // Three keys per ideal bucket, inserted out of order, so most entries
+  // are displaced.
+  for i in (0..30).rev() {
+    for j in 0..3 {
+      table.insert(i + j * bucket_count, i);
+    }
+  }
+  assert_eq!(table.bucket_count() as u64, bucket_count);
+
+  // With an identity hash the ideal bucket is the key itself modulo the
+  // bucket count, so key + distance recovers the physical slot.
+  let mask = bucket_count - 1;
+  let slots: Vec<u64> = table
+    .entries_with_distance()
+    .map(|(key, _, distance)| (key & mask) + distance as u64)
+    .collect();
+  assert!(slots.windows(2).all(|pair| pair[0] < pair[1]));
+
+  let with_distance: HashSet<(u64, u64)> = table
+    .entries_with_distance()
+    .map(|(k, v, _)| (*k, *v))
+    .collect();
+  let plain: HashSet<(u64, u64)> =
+    table.iter().map(|(k, v)| (*k, *v)).collect();
+  assert_eq!(with_distance, plain);
+  assert_eq!(with_distance.len(), table.len());
+  assert!(table.entries_with_distance().any(|(_, _, d)| d > 0));
+}
+
+#[test]
+fn test_any_and_nth_entry() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  assert_eq!(table.any_entry(), None);
+  assert_eq!(table.nth_entry(0), None);
+
+  for i in 0..50 {
+    table.insert(i, i * 2);
+  }
+
+  let (key, value) = table.any_entry().unwrap();
+  assert_eq!(table.get(key), Some(value));
+  assert_eq!(table.any_entry(), table.iter().next());
+
+  let in_order: Vec<(&i32, &i32)> = table.iter().collect();
+  assert_eq!(table.nth_entry(table.len() - 1), in_order.last().copied());
+  assert_eq!(table.nth_entry(17), Some(in_order[17]));
+  assert_eq!(table.nth_entry(table.len()), None);
+}
+
+#[test]
+fn test_iter_on_empty_tables() {
+  let unallocated: HashTable<i32, i32> = HashTable::with_capacity(0);
+  assert_eq!(unallocated.bucket_count(), 0);
+  assert_eq!(unallocated.iter().len(), 0);
+  assert_eq!(unallocated.iter().next(), None);
+
+  let mut presized: HashTable<i32, i32> = HashTable::with_capacity(100_000);
+  assert!(presized.bucket_count() > 0);
+  assert_eq!(presized.iter().next(), None);
+
+  presized.insert(1, 1);
+  assert_eq!(presized.iter().collect::<Vec<_>>(), vec![(&1, &1)]);
+  presized.remove(&1);
+  assert_eq!(presized.iter().len(), 0);
+  assert_eq!(presized.iter().next(), None);
+}
@@ -0,0 +1,25 @@
+#![cfg(feature = "rayon")]
+
+extern crate sherwood_table;
+
+use rayon::iter::IntoParallelRefIterator;
+use rayon::iter::ParallelIterator;
+
+use sherwood_table::HashTable;
+
+#[test]
+fn test_par_iter_sum() {
+  let table: HashTable<u64, u64> = (0..100_000).map(|i| (i, i * 3)).collect();
+
+  let sequential: u64 = table.iter().map(|(_, value)| *value).sum();
+  let parallel: u64 = table.par_iter().map(|(_, value)| *value).sum();
+
+  assert_eq!(parallel, sequential);
+  assert_eq!(table.par_iter().count(), table.len());
+}
+
+#[test]
+fn test_par_iter_empty() {
+  let table: HashTable<u64, u64> = HashTable::new();
+  assert_eq!(table.par_iter().count(), 0);
+}
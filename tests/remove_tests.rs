@@ -1,10 +1,64 @@
 extern crate sherwood_table;
 
+mod common;
+
+use std::collections::HashMap;
 use std::hash::BuildHasher;
 use std::hash::Hasher;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 
 use sherwood_table::HashTable;
 
+use common::allocations;
+use common::CountingAllocator;
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[derive(Clone)]
+struct FixedHasher;
+impl Hasher for FixedHasher {
+  fn finish(&self) -> u64 {
+    0
+  }
+  fn write(&mut self, _bytes: &[u8]) {}
+}
+
+#[derive(Clone)]
+struct FixedHashBuilder;
+impl BuildHasher for FixedHashBuilder {
+  type Hasher = FixedHasher;
+  fn build_hasher(&self) -> Self::Hasher {
+    FixedHasher
+  }
+}
+
+// Gives every run of eight consecutive keys the same hash, so the table is
+// full of clusters for removals to shift back.
+#[derive(Clone, Default)]
+struct ClusteringHasher(u64);
+impl Hasher for ClusteringHasher {
+  fn finish(&self) -> u64 {
+    self.0 / 8
+  }
+  fn write(&mut self, bytes: &[u8]) {
+    for &byte in bytes.iter().rev() {
+      self.0 = (self.0 << 8) | byte as u64;
+    }
+  }
+}
+
+#[derive(Clone)]
+struct ClusteringHashBuilder;
+impl BuildHasher for ClusteringHashBuilder {
+  type Hasher = ClusteringHasher;
+  fn build_hasher(&self) -> Self::Hasher {
+    ClusteringHasher(0)
+  }
+}
+
 #[test]
 fn test_remove_single_item() {
   let mut table: HashTable<i32, String> = HashTable::new();
@@ -48,24 +102,6 @@ fn test_remove_and_reinsert() {
 
 #[test]
 fn test_remove_with_collisions() {
-  #[derive(Clone)]
-  struct FixedHasher;
-  impl Hasher for FixedHasher {
-    fn finish(&self) -> u64 {
-      0
-    }
-    fn write(&mut self, _bytes: &[u8]) {}
-  }
-
-  #[derive(Clone)]
-  struct FixedHashBuilder;
-  impl BuildHasher for FixedHashBuilder {
-    type Hasher = FixedHasher;
-    fn build_hasher(&self) -> Self::Hasher {
-      FixedHasher
-    }
-  }
-
   let mut table: HashTable<i32, String, FixedHashBuilder> =
     HashTable::with_hasher(FixedHashBuilder);
 
@@ -200,3 +236,566 @@ fn test_iterator_after_remove() {
   assert!(!collected.iter().any(|(k, _)| *k == 5));
   assert!(!collected.iter().any(|(k, _)| *k == 7));
 }
+
+#[test]
+fn test_retain() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+
+  for i in 0..1000 {
+    table.insert(i, i * 2);
+  }
+
+  let mut visited = 0;
+  table.retain(|k, v| {
+    visited += 1;
+    *v += 1;
+    k % 2 == 0
+  });
+
+  assert_eq!(visited, 1000);
+  assert_eq!(table.len(), 500);
+
+  for i in 0..1000 {
+    if i % 2 == 0 {
+      assert_eq!(table.get(&i), Some(&(i * 2 + 1)));
+    } else {
+      assert_eq!(table.get(&i), None);
+    }
+  }
+}
+
+#[test]
+fn test_split_off() {
+  let mut odd: HashTable<i32, String> = HashTable::new();
+  for i in 0..100 {
+    odd.insert(i, format!("value_{}", i));
+  }
+
+  let even = odd.split_off(|k, _| k % 2 == 0);
+
+  assert_eq!(odd.len(), 50);
+  assert_eq!(even.len(), 50);
+  for i in 0..100 {
+    let (kept, other) = if i % 2 == 0 {
+      (&even, &odd)
+    } else {
+      (&odd, &even)
+    };
+    assert_eq!(kept.get(&i), Some(&format!("value_{}", i)));
+    assert!(!other.contains_key(&i));
+  }
+
+  let rest = odd.split_off(|_, v| v.ends_with('7'));
+  assert_eq!(rest.len(), 10);
+  assert_eq!(odd.len(), 40);
+  assert!(odd.split_off(|_, _| false).is_empty());
+}
+
+#[test]
+fn test_retain_shrink() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  for i in 0..10_000 {
+    table.insert(i, i);
+  }
+  let bucket_count = table.bucket_count();
+
+  table.retain_shrink(|k, v| {
+    *v += 1;
+    k % 20 == 0
+  });
+
+  assert_eq!(table.len(), 500);
+  assert!(table.bucket_count() < bucket_count / 8);
+  assert_eq!(
+    table.bucket_count(),
+    HashTable::<i32, i32>::capacity_for(500)
+  );
+  for i in 0..10_000 {
+    let expected = if i % 20 == 0 { Some(&(i + 1)) } else { None };
+    assert_eq!(table.get(&i), expected);
+  }
+
+  // Keeping everything leaves the table as it is, without reallocating.
+  let bucket_count = table.bucket_count();
+  let before = allocations();
+  table.retain_shrink(|_, _| true);
+  assert_eq!(allocations(), before);
+  assert_eq!(table.len(), 500);
+  assert_eq!(table.bucket_count(), bucket_count);
+
+  // So does dropping a few that still need the same bucket count.
+  let before = allocations();
+  table.retain_shrink(|k, _| *k != 0);
+  assert_eq!(allocations(), before);
+  assert_eq!(table.len(), 499);
+  assert_eq!(table.bucket_count(), bucket_count);
+  table.insert(1, 1);
+  assert_eq!(table.get(&20), Some(&21));
+  assert_eq!(table.get(&1), Some(&1));
+}
+
+#[test]
+fn test_retain_mut() {
+  let mut table: HashTable<i32, i32> = (0..100).map(|i| (i, i)).collect();
+
+  table.retain_mut(|_, v| {
+    *v += 10;
+    *v <= 50
+  });
+
+  assert_eq!(table.len(), 41);
+  for i in 0..100 {
+    let expected = (i + 10 <= 50).then_some(i + 10);
+    assert_eq!(table.get(&i).copied(), expected);
+  }
+}
+
+#[test]
+fn test_retain_with_collisions() {
+  let mut table: HashTable<i32, i32, FixedHashBuilder> =
+    HashTable::with_hasher(FixedHashBuilder);
+
+  for i in 0..40 {
+    table.insert(i, i);
+  }
+
+  table.retain(|k, _| k % 3 == 0);
+
+  assert_eq!(table.len(), 14);
+  for i in 0..40 {
+    assert_eq!(table.get(&i).is_some(), i % 3 == 0);
+  }
+}
+
+#[test]
+fn test_extract_if() {
+  let mut table: HashTable<i32, i32> = (0..100).map(|i| (i, i * 10)).collect();
+
+  let mut extracted: Vec<(i32, i32)> =
+    table.extract_if(|k, _| k % 2 == 1).collect();
+  extracted.sort();
+
+  assert_eq!(
+    extracted,
+    (0..100)
+      .filter(|i| i % 2 == 1)
+      .map(|i| (i, i * 10))
+      .collect::<Vec<_>>()
+  );
+  assert_eq!(table.len(), 50);
+  for i in 0..100 {
+    assert_eq!(table.get(&i).is_some(), i % 2 == 0);
+  }
+}
+
+#[test]
+fn test_extract_if_with_collisions_dropped_early() {
+  let mut table: HashTable<i32, i32, FixedHashBuilder> =
+    HashTable::with_hasher(FixedHashBuilder);
+  for i in 0..40 {
+    table.insert(i, i);
+  }
+
+  let first = table
+    .extract_if(|k, v| {
+      *v += 1;
+      k % 3 != 0
+    })
+    .next();
+  assert!(first.is_some());
+
+  assert_eq!(table.len(), 14);
+  for i in 0..40 {
+    let expected = if i % 3 == 0 { Some(&(i + 1)) } else { None };
+    assert_eq!(table.get(&i), expected);
+  }
+}
+
+// Debug builds check the whole layout after every resize, so the growing
+// and shrinking here doubles as a consistency check of insert and remove.
+#[test]
+fn test_insert_remove_mix_keeps_layout_valid() {
+  let mut table: HashTable<u32, u32, ClusteringHashBuilder> =
+    HashTable::with_hasher(ClusteringHashBuilder);
+  table.set_min_load_factor(0.2);
+  let mut expected = HashMap::new();
+
+  let mut state = 12345u32;
+  for round in 0..20_000u32 {
+    state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+    let key = (state >> 8) % 4096;
+    // Alternate between phases that mostly insert and mostly remove so
+    // the table repeatedly grows and shrinks.
+    let insert_phase = (round / 2500).is_multiple_of(2);
+    let against_phase = state.is_multiple_of(4);
+    if insert_phase != against_phase {
+      assert_eq!(table.insert(key, round), expected.insert(key, round));
+    } else {
+      assert_eq!(table.remove(&key), expected.remove(&key));
+    }
+  }
+
+  assert_eq!(table.len(), expected.len());
+  for (key, value) in &expected {
+    assert_eq!(table.get(key), Some(value));
+  }
+}
+
+#[test]
+fn test_remove_fast_with_collisions() {
+  let mut table: HashTable<i32, i32, FixedHashBuilder> =
+    HashTable::with_hasher(FixedHashBuilder);
+  for i in 0..10 {
+    table.insert(i, i * 10);
+  }
+
+  assert_eq!(table.remove_fast(&3), Some(30));
+  assert_eq!(table.remove_fast(&4), Some(40));
+  assert_eq!(table.remove_fast(&4), None);
+  assert_eq!(table.len(), 8);
+
+  // Keys stored past the tombstones are still found.
+  for i in (0..10).filter(|i| ![3, 4].contains(i)) {
+    assert_eq!(table.get(&i), Some(&(i * 10)));
+  }
+  assert_eq!(table.get(&3), None);
+  assert!(!table.contains_key(&4));
+
+  // Insertions, overwrites and regular removals walk past them too.
+  table.insert(10, 100);
+  table.insert(5, 55);
+  assert_eq!(table.remove(&2), Some(20));
+  assert_eq!(table.remove(&9), Some(90));
+  *table.get_mut(&8).unwrap() += 1;
+  assert_eq!(table.len(), 7);
+
+  let mut remaining: Vec<(i32, i32)> =
+    table.iter().map(|(k, v)| (*k, *v)).collect();
+  remaining.sort();
+  assert_eq!(
+    remaining,
+    vec![
+      (0, 0),
+      (1, 10),
+      (5, 55),
+      (6, 60),
+      (7, 70),
+      (8, 81),
+      (10, 100)
+    ]
+  );
+
+  table.insert(3, 3);
+  assert_eq!(table.get(&3), Some(&3));
+  assert_eq!(table.len(), 8);
+}
+
+#[test]
+fn test_remove_fast_mixed_with_remove() {
+  let mut table: HashTable<u32, u32, ClusteringHashBuilder> =
+    HashTable::with_hasher(ClusteringHashBuilder);
+  let mut expected = HashMap::new();
+
+  let mut state = 777u32;
+  for round in 0..20_000u32 {
+    state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+    let key = (state >> 8) % 2048;
+    match state % 3 {
+      0 => assert_eq!(table.insert(key, round), expected.insert(key, round)),
+      1 => assert_eq!(table.remove_fast(&key), expected.remove(&key)),
+      _ => assert_eq!(table.remove(&key), expected.remove(&key)),
+    }
+  }
+
+  assert_eq!(table.len(), expected.len());
+  assert_eq!(table.iter().count(), expected.len());
+  for key in 0..2048 {
+    assert_eq!(table.get(&key), expected.get(&key));
+  }
+
+  table.clear();
+  assert!(table.is_empty());
+  assert_eq!(table.get(&0), None);
+}
+
+#[test]
+fn test_tombstones_interleaved_with_inserts() {
+  let mut table: HashTable<u32, u32, ClusteringHashBuilder> =
+    HashTable::with_hasher(ClusteringHashBuilder);
+  for key in 0..64 {
+    table.insert(key, key);
+  }
+
+  for round in 1..20 {
+    // Knock out every third key, put half of them straight back and
+    // check nobody stops short at a tombstone.
+    for key in (round % 3..64).step_by(3) {
+      assert_eq!(table.remove_fast(&key), Some(key + 100 * (round - 1)));
+    }
+    for key in 0..64 {
+      let expected = if key % 3 == round % 3 {
+        None
+      } else {
+        Some(key + 100 * (round - 1))
+      };
+      assert_eq!(table.get(&key).copied(), expected, "round {}", round);
+    }
+    for key in 0..64 {
+      table.insert(key, key + 100 * round);
+    }
+    assert_eq!(table.len(), 64);
+    for key in 0..64 {
+      assert_eq!(table.get(&key), Some(&(key + 100 * round)));
+    }
+  }
+}
+
+#[test]
+fn test_insert_reuses_tombstone_no_key_probes_through() {
+  let mut table: HashTable<u32, u32, ClusteringHashBuilder> =
+    HashTable::with_hasher(ClusteringHashBuilder);
+  // Keys 0 and 1 share bucket 0, key 8 wants bucket 1 and sits one past it.
+  table.insert(0, 0);
+  table.insert(1, 1);
+  table.insert(8, 8);
+  assert_eq!(table.max_probe_length(), 1);
+
+  // Leaves a tombstone in bucket 0, then shifts key 8 home behind it.
+  assert_eq!(table.remove_fast(&0), Some(0));
+  assert_eq!(table.remove(&1), Some(1));
+  assert_eq!(table.get(&8), Some(&8));
+
+  // Key 2 can take the tombstone instead of pushing key 8 out of its home.
+  table.insert(2, 2);
+  assert_eq!(table.max_probe_length(), 0);
+  assert_eq!(table.get(&2), Some(&2));
+  assert_eq!(table.get(&8), Some(&8));
+}
+
+#[test]
+fn test_remove_fast_churn_keeps_bucket_count_bounded() {
+  for live in [1000u32, 20_000] {
+    let mut table: HashTable<u32, u32> = HashTable::new();
+    for key in 0..live {
+      table.insert(key, key);
+    }
+    let bucket_count = table.bucket_count();
+
+    // Oldest key out, a new one in: the live count never changes.
+    for key in live..live * 20 {
+      assert_eq!(table.remove_fast(&(key - live)), Some(key - live));
+      table.insert(key, key);
+    }
+
+    assert!(
+      table.bucket_count() <= bucket_count * 2,
+      "{} live keys grew the table from {} to {} buckets",
+      live,
+      bucket_count,
+      table.bucket_count()
+    );
+    assert_eq!(table.len(), live as usize);
+    for key in live * 19..live * 20 {
+      assert_eq!(table.get(&key), Some(&key));
+    }
+  }
+}
+
+#[test]
+fn test_remove_fast_churn_clears_tombstones() {
+  let mut table: HashTable<u32, u32, FixedHashBuilder> =
+    HashTable::with_hasher(FixedHashBuilder);
+  table.reserve(256);
+  let bucket_count = table.bucket_count();
+  for key in 0..16 {
+    table.insert(key, key);
+  }
+
+  // Every key shares one bucket, so each removal leaves a tombstone at
+  // the front of the cluster and each insertion walks past all of them.
+  // Far below the load factor, only the tombstone count bounds that walk.
+  for key in 16..5000 {
+    assert_eq!(table.remove_fast(&(key - 16)), Some(key - 16));
+    table.insert(key, key);
+    assert!(table.max_probe_length() as usize <= 16 + bucket_count / 4);
+  }
+
+  assert_eq!(table.bucket_count(), bucket_count);
+  assert_eq!(table.len(), 16);
+  for key in 4984..5000 {
+    assert_eq!(table.get(&key), Some(&key));
+  }
+}
+
+#[test]
+fn test_rebuild_after_remove_fast() {
+  let mut table: HashTable<i32, i32, FixedHashBuilder> =
+    HashTable::with_hasher(FixedHashBuilder);
+  // Leave room for the tombstones below, which count towards the load.
+  table.reserve(64);
+  for key in 0..32 {
+    table.insert(key, key);
+  }
+  let bucket_count = table.bucket_count();
+  assert_eq!(table.max_probe_length(), 31);
+
+  // Every key shares one bucket, so these all leave tombstones at the
+  // front of the cluster and new keys still have to go to the back.
+  for key in 0..16 {
+    assert_eq!(table.remove_fast(&key), Some(key));
+  }
+  for key in 32..40 {
+    table.insert(key, key);
+  }
+  assert_eq!(table.max_probe_length(), 39);
+
+  table.rebuild();
+  assert_eq!(table.max_probe_length(), 23);
+  assert_eq!(table.bucket_count(), bucket_count);
+  assert_eq!(table.len(), 24);
+  for key in 16..40 {
+    assert_eq!(table.get(&key), Some(&key));
+  }
+
+  let mut empty: HashTable<i32, i32> = HashTable::new();
+  empty.rebuild();
+  assert!(empty.is_empty());
+}
+
+#[test]
+fn test_remove_entry() {
+  let mut table: HashTable<String, i32> = HashTable::new();
+
+  table.insert("apple".to_string(), 1);
+  table.insert("banana".to_string(), 2);
+
+  assert_eq!(table.remove_entry("apple"), Some(("apple".to_string(), 1)));
+  assert_eq!(table.remove_entry("apple"), None);
+  assert_eq!(table.remove_entry("cherry"), None);
+
+  assert_eq!(table.len(), 1);
+  assert_eq!(table.get("banana"), Some(&2));
+}
+
+#[test]
+fn test_min_load_factor_shrinks_on_remove() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  assert_eq!(table.min_load_factor(), 0.0);
+  table.set_min_load_factor(0.2);
+
+  for i in 0..1000 {
+    table.insert(i, i);
+  }
+  let capacity = table.capacity();
+
+  for i in 0..900 {
+    assert_eq!(table.remove(&i), Some(i));
+  }
+
+  assert_eq!(table.len(), 100);
+  assert!(table.capacity() < capacity);
+  for i in 900..1000 {
+    assert_eq!(table.get(&i), Some(&i));
+  }
+}
+
+#[test]
+fn test_shrink_ratio_shrinks_once() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  assert_eq!(table.shrink_ratio(), 0.0);
+  table.set_shrink_ratio(0.25);
+
+  for i in 0..10_000 {
+    table.insert(i, i);
+  }
+  let bucket_count = table.bucket_count();
+
+  let mut snapshots = vec![bucket_count];
+  for i in 0..9_000 {
+    assert_eq!(table.remove(&i), Some(i));
+    if table.bucket_count() != *snapshots.last().unwrap() {
+      snapshots.push(table.bucket_count());
+    }
+  }
+
+  // One shrink after a quarter of the buckets' worth of removals; the
+  // count then starts over against the smaller bucket count, and the
+  // remaining removals stay below that.
+  assert_eq!(snapshots.len(), 2);
+  assert!(snapshots[1] <= bucket_count / 4);
+  assert_eq!(table.len(), 1_000);
+  for i in 9_000..10_000 {
+    assert_eq!(table.get(&i), Some(&i));
+  }
+}
+
+#[test]
+fn test_churn_at_load_boundary_does_not_thrash() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  table.set_min_load_factor(0.2);
+  table.set_shrink_ratio(0.01);
+
+  let mut next = 0;
+  while table.len() < 1000 || table.len() < table.capacity() {
+    table.insert(next, next);
+    next += 1;
+  }
+
+  let resizes = Arc::new(AtomicUsize::new(0));
+  let counter = Arc::clone(&resizes);
+  table.set_resize_hook(move |_, _| {
+    counter.fetch_add(1, Ordering::Relaxed);
+  });
+
+  // Each insert crosses the growth threshold and each remove drops back
+  // below it.
+  for i in 0..10_000 {
+    table.insert(next + i, i);
+    assert_eq!(table.remove(&(next + i)), Some(i));
+  }
+
+  assert!(resizes.load(Ordering::Relaxed) < 5);
+  for i in 0..next {
+    assert_eq!(table.get(&i), Some(&i));
+  }
+}
+
+#[test]
+fn test_removal_does_not_shrink_by_default() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  for i in 0..1000 {
+    table.insert(i, i);
+  }
+  let capacity = table.capacity();
+
+  for i in 0..1000 {
+    table.remove(&i);
+  }
+  assert!(table.is_empty());
+  assert_eq!(table.capacity(), capacity);
+}
+
+#[test]
+#[should_panic(expected = "min load factor")]
+fn test_min_load_factor_above_max() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  table.set_min_load_factor(0.5);
+}
+
+#[test]
+fn test_min_load_factor_range_follows_max() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  table.set_min_load_factor(0.24);
+  assert_eq!(table.min_load_factor(), 0.24);
+
+  table.set_max_load_factor(0.9);
+  table.set_min_load_factor(0.44);
+  assert_eq!(table.min_load_factor(), 0.44);
+}
+
+#[test]
+#[should_panic(expected = "min load factor")]
+fn test_min_load_factor_at_half_max() {
+  let mut table: HashTable<i32, i32> = HashTable::new();
+  table.set_min_load_factor(0.25);
+}
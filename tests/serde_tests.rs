@@ -0,0 +1,33 @@
+#![cfg(feature = "serde")]
+
+extern crate sherwood_table;
+
+use sherwood_table::HashTable;
+
+#[test]
+fn test_serde_round_trip() {
+  let mut table: HashTable<u32, String> = HashTable::new();
+
+  for i in 0..1000 {
+    table.insert(i, format!("value_{}", i));
+  }
+
+  let json = serde_json::to_string(&table).unwrap();
+  let restored: HashTable<u32, String> = serde_json::from_str(&json).unwrap();
+
+  assert_eq!(restored.len(), 1000);
+  for i in 0..1000 {
+    assert_eq!(restored.get(&i), Some(&format!("value_{}", i)));
+  }
+}
+
+#[test]
+fn test_serde_serializes_logical_contents() {
+  let mut table: HashTable<String, i32> = HashTable::new();
+  table.insert("only".to_string(), 1);
+
+  assert_eq!(serde_json::to_string(&table).unwrap(), r#"{"only":1}"#);
+
+  let empty: HashTable<String, i32> = serde_json::from_str("{}").unwrap();
+  assert!(empty.is_empty());
+}
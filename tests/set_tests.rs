@@ -0,0 +1,56 @@
+extern crate sherwood_table;
+
+use std::collections::HashSet;
+
+use sherwood_table::SherwoodSet;
+
+#[test]
+fn test_set_membership() {
+  let mut set: SherwoodSet<String> = SherwoodSet::new();
+  assert!(set.is_empty());
+
+  assert!(set.insert("a".to_string()));
+  assert!(set.insert("b".to_string()));
+  assert!(!set.insert("a".to_string()));
+  assert_eq!(set.len(), 2);
+
+  assert!(set.contains("a"));
+  assert!(!set.contains("c"));
+
+  assert!(set.remove("a"));
+  assert!(!set.remove("a"));
+  assert!(!set.contains("a"));
+  assert_eq!(set.iter().collect::<Vec<_>>(), vec!["b"]);
+}
+
+#[test]
+fn test_set_union_of_disjoint_sets() {
+  let evens: SherwoodSet<i32> = (0..100).map(|i| i * 2).collect();
+  let odds: SherwoodSet<i32> = (0..50).map(|i| i * 2 + 1).collect();
+
+  let union: HashSet<i32> = evens.union(&odds).copied().collect();
+  assert_eq!(evens.union(&odds).count(), 150);
+  assert_eq!(union.len(), 150);
+  assert!(union.contains(&198) && union.contains(&99));
+}
+
+#[test]
+fn test_set_operations() {
+  let a: SherwoodSet<i32> = (0..10).collect();
+  let b: SherwoodSet<i32> = (5..15).collect();
+
+  let mut union: Vec<i32> = a.union(&b).copied().collect();
+  union.sort();
+  assert_eq!(union, (0..15).collect::<Vec<_>>());
+
+  let mut intersection: Vec<i32> = a.intersection(&b).copied().collect();
+  intersection.sort();
+  assert_eq!(intersection, (5..10).collect::<Vec<_>>());
+
+  let mut difference: Vec<i32> = a.difference(&b).copied().collect();
+  difference.sort();
+  assert_eq!(difference, (0..5).collect::<Vec<_>>());
+
+  assert_eq!(a.clone(), a);
+  assert_ne!(a, b);
+}